@@ -0,0 +1,269 @@
+/// Keywords that may precede the name in a top-level declaration.
+const DECLARATION_KEYWORDS: &[&str] = &[
+    "export", "pub", "let", "rec", "type", "record", "infix", "infixl", "infixr",
+];
+
+/// Top-level lines that do not declare anything.
+const NON_DECLARATIONS: &[&str] = &["import", "from", "open"];
+
+/// The documentation of one standard library module.
+#[derive(Debug, Default, PartialEq)]
+pub struct ModuleDocs {
+    /// The module path relative to the standard library, without `.wm`.
+    pub name: String,
+    /// The comment block at the top of the file.
+    pub doc: String,
+    pub declarations: Vec<Declaration>,
+}
+
+/// A top-level declaration and the doc comment right above it.
+#[derive(Debug, PartialEq)]
+pub struct Declaration {
+    pub name: String,
+    /// The first line of the declaration.
+    pub header: String,
+    pub doc: String,
+}
+
+impl ModuleDocs {
+    /// Extracts doc comments from a module's source.
+    ///
+    /// A run of `-- ` comments at the start of a line documents the top-level
+    /// declaration on the line right after it. The first run, when a blank line
+    /// separates it from what follows, documents the module itself. Any line
+    /// that starts in the first column and is neither a comment nor an import
+    /// is a declaration.
+    pub fn parse(name: &str, source: &str) -> Self {
+        let mut docs = Self {
+            name: name.to_string(),
+            ..Self::default()
+        };
+        let mut comment = Vec::new();
+        let mut seen_code = false;
+        for line in source.lines() {
+            if let Some(text) = line.strip_prefix("--") {
+                comment.push(text.strip_prefix(' ').unwrap_or(text).trim_end());
+                continue;
+            }
+            if line.trim().is_empty() {
+                if !seen_code && docs.doc.is_empty() && !comment.is_empty() {
+                    docs.doc = comment.join("\n");
+                }
+                comment.clear();
+                continue;
+            }
+            seen_code = true;
+            if line.starts_with(char::is_whitespace) {
+                comment.clear();
+                continue;
+            }
+            if let Some(name) = declared_name(line) {
+                docs.declarations.push(Declaration {
+                    name,
+                    header: line.trim_end().to_string(),
+                    doc: comment.join("\n"),
+                });
+            }
+            comment.clear();
+        }
+        docs
+    }
+
+    /// Renders the module as Markdown, or only the declarations named `only`.
+    pub fn to_markdown(&self, only: Option<&str>) -> String {
+        let mut markdown = format!("# {}\n", self.name);
+        if only.is_none() && !self.doc.is_empty() {
+            markdown.push_str(&format!("\n{}\n", self.doc));
+        }
+        for declaration in &self.declarations {
+            if only.is_some_and(|only| only != declaration.name) {
+                continue;
+            }
+            markdown.push('\n');
+            markdown.push_str(&declaration.to_markdown());
+        }
+        markdown
+    }
+}
+
+impl Declaration {
+    pub fn to_markdown(&self) -> String {
+        let mut markdown = format!("## {}\n\n```workman\n{}\n```\n", self.name, self.header);
+        if !self.doc.is_empty() {
+            markdown.push_str(&format!("\n{}\n", self.doc));
+        }
+        markdown
+    }
+}
+
+/// Finds the documentation for `query`: a module, `module.name`, or the name
+/// of a declaration in any module.
+///
+/// Returns a title and Markdown for each match.
+pub fn lookup(modules: &[ModuleDocs], query: &str) -> Vec<(String, String)> {
+    if let Some(module) = modules.iter().find(|module| module.name == query) {
+        return vec![(module.name.clone(), module.to_markdown(None))];
+    }
+
+    let (module_name, name) = match query.rsplit_once('.') {
+        Some((module_name, name)) => (Some(module_name), name),
+        None => (None, query),
+    };
+    modules
+        .iter()
+        .filter(|module| module_name.is_none_or(|module_name| module.name == module_name))
+        .filter(|module| {
+            module
+                .declarations
+                .iter()
+                .any(|declaration| declaration.name == name)
+        })
+        .map(|module| {
+            (
+                format!("{}.{name}", module.name),
+                module.to_markdown(Some(name)),
+            )
+        })
+        .collect()
+}
+
+/// The entries of the searchable docs index: each module under its name and
+/// each declaration under `module.name`, both as Markdown.
+pub fn index_entries(modules: &[ModuleDocs]) -> Vec<(String, String)> {
+    let mut entries = Vec::new();
+    for module in modules {
+        entries.push((module.name.clone(), module.to_markdown(None)));
+        for declaration in &module.declarations {
+            entries.push((
+                format!("{}.{}", module.name, declaration.name),
+                module.to_markdown(Some(&declaration.name)),
+            ));
+        }
+    }
+    entries
+}
+
+/// The name a top-level line declares, if it declares one.
+fn declared_name(line: &str) -> Option<String> {
+    let mut words = line.split_whitespace().peekable();
+    if NON_DECLARATIONS.contains(words.peek()?) {
+        return None;
+    }
+    while words
+        .peek()
+        .is_some_and(|word| DECLARATION_KEYWORDS.contains(word))
+    {
+        words.next();
+    }
+    let word = words.next()?;
+    if let Some(operator) = word.strip_prefix('(') {
+        let operator = operator.split(')').next()?;
+        return (!operator.is_empty()).then(|| format!("({operator})"));
+    }
+    let name = word
+        .split(|c: char| !(c.is_alphanumeric() || c == '_' || c == '\''))
+        .next()?;
+    (!name.is_empty()).then(|| name.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = "\
+-- Functions over lists.
+-- Lists are immutable.
+
+import std/option
+
+-- Applies `f` to every element.
+let map = (f, xs) => {
+  -- Not a doc comment.
+  match xs { ... }
+}
+
+let length = (xs) => fold((n, _) => n + 1, 0, xs)
+
+-- Concatenates two lists.
+let (++) = (xs, ys) => append(xs, ys)
+
+-- A list with a known first element.
+-- Stands alone.
+
+type NonEmpty a = NonEmpty a (List a)
+";
+
+    #[test]
+    fn extracts_module_and_declaration_docs() {
+        let docs = ModuleDocs::parse("list", SOURCE);
+        assert_eq!(docs.doc, "Functions over lists.\nLists are immutable.");
+        let declarations = docs
+            .declarations
+            .iter()
+            .map(|declaration| (declaration.name.as_str(), declaration.doc.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            declarations,
+            [
+                ("map", "Applies `f` to every element."),
+                ("length", ""),
+                ("(++)", "Concatenates two lists."),
+                ("NonEmpty", ""),
+            ]
+        );
+        assert_eq!(docs.declarations[0].header, "let map = (f, xs) => {");
+    }
+
+    #[test]
+    fn a_comment_right_above_the_first_declaration_is_not_the_module_doc() {
+        let docs = ModuleDocs::parse("option", "-- Nothing or a value.\ntype Option a = ...\n");
+        assert_eq!(docs.doc, "");
+        assert_eq!(docs.declarations[0].doc, "Nothing or a value.");
+    }
+
+    #[test]
+    fn looks_up_modules_and_declarations() {
+        let modules = [
+            ModuleDocs::parse("list", SOURCE),
+            ModuleDocs::parse("data/array", "let map = (f, xs) => xs\n"),
+        ];
+        let titles = |query| {
+            lookup(&modules, query)
+                .into_iter()
+                .map(|(title, _)| title)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(titles("list"), ["list"]);
+        assert_eq!(titles("map"), ["list.map", "data/array.map"]);
+        assert_eq!(titles("data/array.map"), ["data/array.map"]);
+        assert_eq!(titles("(++)"), ["list.(++)"]);
+        assert!(titles("filter").is_empty());
+    }
+
+    #[test]
+    fn indexes_modules_and_declarations() {
+        let modules = [ModuleDocs::parse("option", "-- Unwraps.\nlet unwrap = (x) => x\n")];
+        let markdown =
+            "# option\n\n## unwrap\n\n```workman\nlet unwrap = (x) => x\n```\n\nUnwraps.\n";
+        assert_eq!(
+            index_entries(&modules),
+            [
+                ("option".to_string(), markdown.to_string()),
+                ("option.unwrap".to_string(), markdown.to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn renders_markdown() {
+        let docs = ModuleDocs::parse("list", SOURCE);
+        assert_eq!(
+            docs.to_markdown(Some("map")),
+            "# list\n\n## map\n\n```workman\nlet map = (f, xs) => {\n```\n\n\
+             Applies `f` to every element.\n"
+        );
+        assert!(docs
+            .to_markdown(None)
+            .starts_with("# list\n\nFunctions over lists.\nLists are immutable.\n\n## map\n"));
+    }
+}
@@ -0,0 +1,253 @@
+use zed_extension_api::{
+    lsp::{Completion, CompletionKind, Symbol, SymbolKind},
+    CodeLabel, CodeLabelSpan,
+};
+
+/// Assembles a [`CodeLabel`] from Workman source, which the Workman grammar
+/// highlights, and literals given one of the grammar's captures directly.
+#[derive(Default)]
+pub struct LabelBuilder {
+    code: String,
+    spans: Vec<CodeLabelSpan>,
+    /// Length of the label text so far, for the filter range.
+    len: usize,
+    filter_range: Option<std::ops::Range<usize>>,
+}
+
+impl LabelBuilder {
+    /// Appends Workman source, highlighted by the grammar.
+    ///
+    /// Source appended right after other source continues the same span, so
+    /// the grammar parses it as one piece of code.
+    pub fn code(mut self, text: &str) -> Self {
+        let start = self.code.len();
+        self.code.push_str(text);
+        let end = self.code.len() as u32;
+        match self.spans.last_mut() {
+            Some(CodeLabelSpan::CodeRange(range)) if range.end == start as u32 => range.end = end,
+            _ => self.spans.push(CodeLabelSpan::code_range(start..self.code.len())),
+        }
+        self.len += text.len();
+        self
+    }
+
+    /// Appends text highlighted with the given capture name.
+    pub fn literal(mut self, text: &str, highlight: Option<&str>) -> Self {
+        self.spans.push(CodeLabelSpan::literal(text, highlight.map(str::to_string)));
+        self.len += text.len();
+        self
+    }
+
+    /// Appends the name that filtering matches against.
+    pub fn name(mut self, text: &str, highlight: Option<&str>) -> Self {
+        self.filter_range = Some(self.len..self.len + text.len());
+        match highlight {
+            Some(_) => self.literal(text, highlight),
+            None => self.code(text),
+        }
+    }
+
+    /// Appends the name followed by ` : <ty>` when there is a type to show.
+    ///
+    /// With a type the whole `name : ty` is Workman source, so the grammar
+    /// highlights the name and the type alike. Without one the name is
+    /// appended as by [`Self::name`].
+    pub fn typed_name(self, name: &str, highlight: Option<&str>, ty: Option<&str>) -> Self {
+        match ty.map(str::trim).filter(|ty| !ty.is_empty()) {
+            Some(ty) => self.name(name, None).code(&format!(" : {ty}")),
+            None => self.name(name, highlight),
+        }
+    }
+
+    pub fn build(self) -> CodeLabel {
+        let filter_range = self.filter_range.unwrap_or(0..self.len);
+        CodeLabel {
+            code: self.code,
+            spans: self.spans,
+            filter_range: filter_range.into(),
+        }
+    }
+}
+
+/// Renders a completion the way it would be written in Workman.
+///
+/// Functions, values and record fields show their type and constructors the
+/// type they build, e.g. `Some : a -> Option a`. Other kinds keep Zed's
+/// default rendering.
+pub fn completion_label(completion: &Completion) -> Option<CodeLabel> {
+    let name = completion.label.as_str();
+    let detail = completion.detail.as_deref();
+    let label = match completion.kind.as_ref()? {
+        CompletionKind::Function
+        | CompletionKind::Method
+        | CompletionKind::Field
+        | CompletionKind::Property
+        | CompletionKind::Variable
+        | CompletionKind::Constant
+        | CompletionKind::Value => LabelBuilder::default().typed_name(name, None, detail),
+        CompletionKind::Constructor | CompletionKind::EnumMember => {
+            LabelBuilder::default().typed_name(name, Some("constructor"), detail)
+        }
+        CompletionKind::Class
+        | CompletionKind::Struct
+        | CompletionKind::Enum
+        | CompletionKind::Interface
+        | CompletionKind::TypeParameter => LabelBuilder::default()
+            .literal("type ", Some("keyword"))
+            .name(name, Some("constructor")),
+        CompletionKind::Module => LabelBuilder::default()
+            .literal("module ", Some("keyword"))
+            .name(name, Some("constructor")),
+        CompletionKind::Keyword => LabelBuilder::default().name(name, Some("keyword")),
+        CompletionKind::Operator => LabelBuilder::default().name(name, Some("operator")),
+        _ => return None,
+    };
+    Some(label.build())
+}
+
+/// Renders an outline or workspace symbol as `<kind> <name>`.
+///
+/// Servers may put a signature in the symbol name, as in
+/// `map : (a -> b) -> List a -> List b`; it is highlighted like a completion's.
+pub fn symbol_label(symbol: &Symbol) -> Option<CodeLabel> {
+    let (name, signature) = match symbol.name.split_once(" : ") {
+        Some((name, signature)) => (name.trim(), Some(signature)),
+        None => (symbol.name.as_str(), None),
+    };
+    let (kind, highlight) = match symbol.kind {
+        SymbolKind::Class
+        | SymbolKind::Struct
+        | SymbolKind::Enum
+        | SymbolKind::Interface
+        | SymbolKind::TypeParameter => ("type", Some("constructor")),
+        SymbolKind::Constructor | SymbolKind::EnumMember => ("constructor", Some("constructor")),
+        SymbolKind::Function | SymbolKind::Method => ("function", None),
+        SymbolKind::Module | SymbolKind::Namespace | SymbolKind::Package => {
+            ("module", Some("constructor"))
+        }
+        SymbolKind::Field | SymbolKind::Property => ("field", None),
+        SymbolKind::Variable | SymbolKind::Constant => ("value", None),
+        _ => return None,
+    };
+    Some(
+        LabelBuilder::default()
+            .literal(kind, Some("keyword"))
+            .literal(" ", None)
+            .typed_name(name, highlight, signature)
+            .build(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Renders the spans as `code[range]` or `literal(text, highlight)`.
+    fn spans(label: &CodeLabel) -> Vec<String> {
+        label
+            .spans
+            .iter()
+            .map(|span| match span {
+                CodeLabelSpan::CodeRange(range) => format!("code[{}..{}]", range.start, range.end),
+                CodeLabelSpan::Literal(literal) => format!(
+                    "literal({:?}, {})",
+                    literal.text,
+                    literal.highlight_name.as_deref().unwrap_or("none")
+                ),
+            })
+            .collect()
+    }
+
+    fn filter_range(label: &CodeLabel) -> std::ops::Range<u32> {
+        label.filter_range.start..label.filter_range.end
+    }
+
+    fn completion(kind: CompletionKind, label: &str, detail: Option<&str>) -> Option<CodeLabel> {
+        completion_label(&Completion {
+            label: label.to_string(),
+            label_details: None,
+            detail: detail.map(str::to_string),
+            kind: Some(kind),
+            insert_text_format: None,
+        })
+    }
+
+    #[test]
+    fn function_completion_is_a_typed_name() {
+        let label =
+            completion(CompletionKind::Function, "map", Some("(a -> b) -> List a")).unwrap();
+        assert_eq!(label.code, "map : (a -> b) -> List a");
+        assert_eq!(spans(&label), ["code[0..24]"]);
+        assert_eq!(filter_range(&label), 0..3);
+    }
+
+    #[test]
+    fn constructor_completion_shows_the_type_it_builds() {
+        let label = completion(CompletionKind::Constructor, "Some", Some("a -> Option a")).unwrap();
+        assert_eq!(label.code, "Some : a -> Option a");
+        assert_eq!(spans(&label), ["code[0..20]"]);
+        assert_eq!(filter_range(&label), 0..4);
+
+        let label = completion(CompletionKind::Constructor, "None", None).unwrap();
+        assert_eq!(label.code, "");
+        assert_eq!(spans(&label), ["literal(\"None\", constructor)"]);
+        assert_eq!(filter_range(&label), 0..4);
+    }
+
+    #[test]
+    fn field_completion_shows_its_type() {
+        let label = completion(CompletionKind::Field, "foo", Some(" Int ")).unwrap();
+        assert_eq!(label.code, "foo : Int");
+        assert_eq!(spans(&label), ["code[0..9]"]);
+        assert_eq!(filter_range(&label), 0..3);
+
+        let label = completion(CompletionKind::Field, "foo", None).unwrap();
+        assert_eq!(label.code, "foo");
+        assert_eq!(spans(&label), ["code[0..3]"]);
+    }
+
+    #[test]
+    fn type_completion_is_prefixed_with_a_keyword() {
+        let label = completion(CompletionKind::Enum, "Option", None).unwrap();
+        assert_eq!(label.code, "");
+        assert_eq!(
+            spans(&label),
+            ["literal(\"type \", keyword)", "literal(\"Option\", constructor)"]
+        );
+        assert_eq!(filter_range(&label), 5..11);
+    }
+
+    #[test]
+    fn symbol_with_a_signature_is_a_typed_name() {
+        let label = symbol_label(&Symbol {
+            kind: SymbolKind::Function,
+            name: "map : (a -> b) -> List a -> List b".to_string(),
+        })
+        .unwrap();
+        assert_eq!(label.code, "map : (a -> b) -> List a -> List b");
+        assert_eq!(
+            spans(&label),
+            ["literal(\"function\", keyword)", "literal(\" \", none)", "code[0..34]"]
+        );
+        assert_eq!(filter_range(&label), 9..12);
+    }
+
+    #[test]
+    fn symbol_without_a_signature_keeps_its_highlight() {
+        let label = symbol_label(&Symbol {
+            kind: SymbolKind::Module,
+            name: "List".to_string(),
+        })
+        .unwrap();
+        assert_eq!(label.code, "");
+        assert_eq!(
+            spans(&label),
+            [
+                "literal(\"module\", keyword)",
+                "literal(\" \", none)",
+                "literal(\"List\", constructor)",
+            ]
+        );
+        assert_eq!(filter_range(&label), 7..11);
+    }
+}
@@ -0,0 +1,523 @@
+use std::collections::BTreeMap;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer};
+use zed_extension_api::{serde_json, Result};
+
+/// Where `lsp.workman-lsp.settings` lives in the user's Zed settings.
+pub const SETTINGS_PATH: &str = "lsp.workman-lsp.settings";
+
+/// Top-level keys that belong to the server's workspace configuration.
+const SERVER_SECTIONS: &[&str] = &["workman"];
+
+/// The JavaScript runtime used to launch the server.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Runtime {
+    #[default]
+    Deno,
+    Node,
+    Bun,
+}
+
+impl Runtime {
+    /// The executable name looked up on PATH.
+    pub fn binary_name(self) -> &'static str {
+        match self {
+            Self::Deno => "deno",
+            Self::Node => "node",
+            Self::Bun => "bun",
+        }
+    }
+
+    /// The flag that makes the runtime evaluate the script that follows.
+    pub fn eval_flag(self) -> &'static str {
+        match self {
+            Self::Deno => "eval",
+            Self::Node | Self::Bun => "-e",
+        }
+    }
+}
+
+/// The `denoConfig` setting: a path, or `false` to leave out `--config`.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub enum DenoConfig {
+    /// Use `deno.json` or `deno.jsonc` next to the server, if present.
+    #[default]
+    Auto,
+    Disabled,
+    Path(String),
+}
+
+impl<'de> Deserialize<'de> for DenoConfig {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            Enabled(bool),
+            Path(String),
+        }
+
+        Ok(match Raw::deserialize(deserializer)? {
+            Raw::Enabled(true) => Self::Auto,
+            Raw::Enabled(false) => Self::Disabled,
+            Raw::Path(path) => Self::Path(path),
+        })
+    }
+}
+
+/// How Deno permissions are granted to the server.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PermissionMode {
+    /// Only the access the server needs, plus whatever `permissions` lists.
+    #[default]
+    Scoped,
+    /// `--allow-all`.
+    AllowAll,
+}
+
+/// The `permissions` setting.
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", default, deny_unknown_fields)]
+pub struct Permissions {
+    pub mode: PermissionMode,
+    /// Extra paths for `--allow-read`.
+    pub read: Vec<String>,
+    /// Extra variables for `--allow-env`.
+    pub env: Vec<String>,
+    /// Hosts for `--allow-net`; network access is denied when empty.
+    pub net: Vec<String>,
+    /// Programs for `--allow-run`; subprocesses are denied when empty.
+    pub run: Vec<String>,
+}
+
+/// The `isolatedDenoDir` setting.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", default, deny_unknown_fields)]
+pub struct IsolatedDenoDir {
+    /// Whether the server gets its own `DENO_DIR` under the extension work directory.
+    pub enabled: bool,
+    /// Changing this discards the current cache and starts a new one.
+    pub generation: String,
+    /// An archive of a Deno cache unpacked into each new cache directory.
+    pub seed_url: Option<String>,
+}
+
+impl Default for IsolatedDenoDir {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            generation: "1".to_string(),
+            seed_url: None,
+        }
+    }
+}
+
+/// The `lsp.workman-lsp.settings` object, validated.
+#[derive(Debug, Clone)]
+pub struct WorkmanSettings {
+    /// Path to a compiled `workman-lsp` executable, launched without Deno.
+    pub server_binary: Option<String>,
+    /// Path to the server entrypoint (`lsp/server/src/server.ts`).
+    pub server_path: Option<String>,
+    /// Root of a Workman checkout containing `lsp/server`.
+    pub server_root: Option<String>,
+    /// A `jsr:`, `npm:` or URL module specifier to run instead of a checkout.
+    pub server_specifier: Option<String>,
+    /// Lockfile passed via `--lock`.
+    pub lockfile: Option<String>,
+    /// Whether Deno may only use modules already in its cache.
+    pub cached_only: bool,
+    /// The Workman standard library, reported to the server.
+    pub stdlib_path: Option<String>,
+    /// Extra worktree-relative directories searched for a Workman checkout.
+    pub server_search_paths: Vec<String>,
+    /// Deno config passed via `--config`.
+    pub deno_config: DenoConfig,
+    /// Extra runtime flags inserted before the server script, from `denoArgs`
+    /// followed by `runtimeArgs`.
+    pub runtime_args: Vec<String>,
+    /// Extra arguments passed to the server after the script.
+    pub server_args: Vec<String>,
+    /// Variables set in (or, when `None`, removed from) the server environment.
+    pub env: BTreeMap<String, Option<String>>,
+    /// Whether and how the server gets a private Deno cache.
+    pub isolated_deno_dir: IsolatedDenoDir,
+    /// Deno permissions granted to the server.
+    pub permissions: Permissions,
+    /// Runtime used to launch the server.
+    pub runtime: Runtime,
+    /// Whether the server bundle and Deno are downloaded when not found locally.
+    pub auto_download: bool,
+    /// Replaces the GitHub release lookup for the server bundle.
+    pub server_download_url: Option<String>,
+    /// Version recorded for a bundle installed from `serverDownloadUrl`.
+    pub server_version: Option<String>,
+}
+
+impl Default for WorkmanSettings {
+    fn default() -> Self {
+        Self {
+            server_binary: None,
+            server_path: None,
+            server_root: None,
+            server_specifier: None,
+            lockfile: None,
+            cached_only: false,
+            stdlib_path: None,
+            server_search_paths: Vec::new(),
+            deno_config: DenoConfig::Auto,
+            runtime_args: Vec::new(),
+            server_args: Vec::new(),
+            env: BTreeMap::new(),
+            isolated_deno_dir: IsolatedDenoDir::default(),
+            permissions: Permissions::default(),
+            runtime: Runtime::default(),
+            auto_download: true,
+            server_download_url: None,
+            server_version: None,
+        }
+    }
+}
+
+impl WorkmanSettings {
+    /// Every key the extension itself understands.
+    const KEYS: &'static [&'static str] = &[
+        "serverBinary",
+        "serverPath",
+        "serverRoot",
+        "serverSpecifier",
+        "lockfile",
+        "cachedOnly",
+        "stdlibPath",
+        "serverSearchPaths",
+        "denoConfig",
+        "denoArgs",
+        "runtimeArgs",
+        "serverArgs",
+        "env",
+        "isolatedDenoDir",
+        "permissions",
+        "runtime",
+        "autoDownload",
+        "serverDownloadUrl",
+        "serverVersion",
+    ];
+
+    /// Validates a raw settings value, returning it alongside any warnings.
+    ///
+    /// Unknown keys are not an error, since the whole object is also forwarded
+    /// to the server as workspace configuration, but each one is reported.
+    pub fn from_value(value: Option<serde_json::Value>) -> Result<(Self, Vec<String>)> {
+        let map = match value {
+            None | Some(serde_json::Value::Null) => return Ok((Self::default(), Vec::new())),
+            Some(serde_json::Value::Object(map)) => map,
+            Some(other) => {
+                return Err(format!(
+                    "`{SETTINGS_PATH}` must be an object, found {}",
+                    describe(&other)
+                ))
+            }
+        };
+
+        let mut warnings = map
+            .keys()
+            .filter(|key| {
+                !Self::KEYS.contains(&key.as_str()) && !SERVER_SECTIONS.contains(&key.as_str())
+            })
+            .map(|key| format!("unknown setting `{SETTINGS_PATH}.{key}`"))
+            .collect::<Vec<_>>();
+
+        // `denoArgs` predates the other runtimes and stays a full synonym.
+        let runtime_args = [
+            field::<Vec<String>>(&map, "denoArgs", "an array of strings")?,
+            field(&map, "runtimeArgs", "an array of strings")?,
+        ]
+        .into_iter()
+        .flatten()
+        .flatten()
+        .collect();
+
+        let defaults = Self::default();
+        let settings = Self {
+            server_binary: field(&map, "serverBinary", "a string")?,
+            server_path: field(&map, "serverPath", "a string")?,
+            server_root: field(&map, "serverRoot", "a string")?,
+            server_specifier: field(&map, "serverSpecifier", "a string")?,
+            lockfile: field(&map, "lockfile", "a string")?,
+            cached_only: field(&map, "cachedOnly", "a boolean")?.unwrap_or_default(),
+            stdlib_path: field(&map, "stdlibPath", "a string")?,
+            server_search_paths: field(&map, "serverSearchPaths", "an array of strings")?
+                .unwrap_or_default(),
+            deno_config: field(&map, "denoConfig", "a string or `false`")?.unwrap_or_default(),
+            runtime_args,
+            server_args: field(&map, "serverArgs", "an array of strings")?.unwrap_or_default(),
+            env: field(&map, "env", "an object of strings or nulls")?.unwrap_or_default(),
+            isolated_deno_dir: field(
+                &map,
+                "isolatedDenoDir",
+                "an object with `enabled`, `generation` and `seedUrl`",
+            )?
+            .unwrap_or_default(),
+            permissions: field(
+                &map,
+                "permissions",
+                "an object with `mode`, `read`, `env`, `net` and `run`",
+            )?
+            .unwrap_or_default(),
+            runtime: field(&map, "runtime", "one of \"deno\", \"node\" or \"bun\"")?
+                .unwrap_or(defaults.runtime),
+            auto_download: field(&map, "autoDownload", "a boolean")?
+                .unwrap_or(defaults.auto_download),
+            server_download_url: field(&map, "serverDownloadUrl", "a string")?,
+            server_version: field(&map, "serverVersion", "a string")?,
+        };
+        if settings.runtime != Runtime::Deno && settings.permissions.mode == PermissionMode::Scoped
+        {
+            warnings.push(format!(
+                "`{SETTINGS_PATH}.permissions` only apply to the deno runtime, so the server \
+                 runs under {} with full access; set `{SETTINGS_PATH}.permissions.mode` to \
+                 \"allowAll\" to acknowledge this",
+                settings.runtime.binary_name()
+            ));
+        }
+        Ok((settings, warnings))
+    }
+}
+
+/// Deserializes one key, naming it and the expected type on failure.
+///
+/// `null` is treated the same as a missing key.
+fn field<T: DeserializeOwned>(
+    map: &serde_json::Map<String, serde_json::Value>,
+    key: &str,
+    expected: &str,
+) -> Result<Option<T>> {
+    match map.get(key) {
+        None | Some(serde_json::Value::Null) => Ok(None),
+        Some(value) => serde_json::from_value(value.clone()).map(Some).map_err(|err| {
+            let mut message = format!(
+                "invalid setting `{SETTINGS_PATH}.{key}`: expected {expected}, found {}",
+                describe(value)
+            );
+            // For objects and arrays the problem is somewhere inside, which
+            // serde's message pinpoints.
+            if value.is_object() || value.is_array() {
+                message.push_str(&format!(" ({err})"));
+            }
+            message
+        }),
+    }
+}
+
+fn describe(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::Null => "null".to_string(),
+        serde_json::Value::Bool(value) => format!("boolean `{value}`"),
+        serde_json::Value::Number(value) => format!("number `{value}`"),
+        serde_json::Value::String(value) => format!("string {value:?}"),
+        serde_json::Value::Array(_) => "an array".to_string(),
+        serde_json::Value::Object(_) => "an object".to_string(),
+    }
+}
+
+/// The configuration the server sees when nothing is set, by section.
+///
+/// The server asks for sections such as `workman.diagnostics`, so each one is
+/// an object rather than `null`. The settings inside the sections have no
+/// defaults here yet: the server does not publish a schema for them, and they
+/// should be filled in from one once it does.
+pub fn default_workspace_configuration() -> serde_json::Value {
+    serde_json::json!({
+        "workman": {
+            "diagnostics": {},
+            "format": {},
+            "inlayHints": {},
+        },
+    })
+}
+
+/// Merges `overlay` into `base`, recursing into objects present in both.
+///
+/// Any other value in `overlay`, including arrays, replaces the one in `base`.
+pub fn deep_merge(base: &mut serde_json::Value, overlay: serde_json::Value) {
+    match (base, overlay) {
+        (serde_json::Value::Object(base), serde_json::Value::Object(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(existing) => deep_merge(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn parse(value: serde_json::Value) -> Result<(WorkmanSettings, Vec<String>)> {
+        WorkmanSettings::from_value(Some(value))
+    }
+
+    #[test]
+    fn missing_or_null_settings_use_defaults() {
+        for value in [None, Some(serde_json::Value::Null)] {
+            let (settings, warnings) = WorkmanSettings::from_value(value).unwrap();
+            assert_eq!(settings.runtime, Runtime::Deno);
+            assert_eq!(settings.deno_config, DenoConfig::Auto);
+            assert!(settings.auto_download);
+            assert!(warnings.is_empty());
+        }
+    }
+
+    #[test]
+    fn parses_known_keys() {
+        let (settings, warnings) = parse(json!({
+            "serverRoot": "~/workman",
+            "runtimeArgs": ["--unstable-kv"],
+            "env": { "NO_COLOR": "1", "DENO_DIR": null },
+            "runtime": "node",
+            "autoDownload": false,
+            "permissions": { "mode": "allowAll" },
+        }))
+        .unwrap();
+        assert_eq!(settings.server_root.as_deref(), Some("~/workman"));
+        assert_eq!(settings.runtime_args, ["--unstable-kv"]);
+        assert_eq!(settings.env["NO_COLOR"].as_deref(), Some("1"));
+        assert_eq!(settings.env["DENO_DIR"], None);
+        assert_eq!(settings.runtime, Runtime::Node);
+        assert!(!settings.auto_download);
+        assert_eq!(settings.permissions.mode, PermissionMode::AllowAll);
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn deno_config_accepts_a_path_or_a_boolean() {
+        let deno_config = |value| parse(json!({ "denoConfig": value })).unwrap().0.deno_config;
+        assert_eq!(deno_config(json!(false)), DenoConfig::Disabled);
+        assert_eq!(deno_config(json!(true)), DenoConfig::Auto);
+        assert_eq!(
+            deno_config(json!("deno.jsonc")),
+            DenoConfig::Path("deno.jsonc".to_string())
+        );
+
+        let err = parse(json!({ "denoConfig": 1 })).unwrap_err();
+        assert_eq!(
+            err,
+            "invalid setting `lsp.workman-lsp.settings.denoConfig`: expected a string or \
+             `false`, found number `1`"
+        );
+    }
+
+    #[test]
+    fn wrong_types_name_the_key_and_expected_type() {
+        let err = parse(json!({ "serverPath": 42 })).unwrap_err();
+        assert_eq!(
+            err,
+            "invalid setting `lsp.workman-lsp.settings.serverPath`: expected a string, \
+             found number `42`"
+        );
+
+        let err = parse(json!({ "runtime": "python" })).unwrap_err();
+        assert!(err.starts_with("invalid setting `lsp.workman-lsp.settings.runtime`"));
+        assert!(err.contains("found string \"python\""));
+
+        // Problems inside arrays and objects also carry serde's description.
+        let err = parse(json!({ "permissions": { "mode": "scoped", "write": [] } })).unwrap_err();
+        assert!(err.contains("found an object ("));
+        assert!(err.contains("unknown field `write`"));
+    }
+
+    #[test]
+    fn deno_args_and_runtime_args_are_both_accepted() {
+        let (settings, warnings) = parse(json!({ "denoArgs": ["--unstable-kv"] })).unwrap();
+        assert_eq!(settings.runtime_args, ["--unstable-kv"]);
+        assert!(warnings.is_empty());
+
+        let (settings, warnings) = parse(json!({
+            "denoArgs": ["--unstable-kv"],
+            "runtimeArgs": ["--v8-flags=--max-old-space-size=4096"],
+        }))
+        .unwrap();
+        assert_eq!(
+            settings.runtime_args,
+            ["--unstable-kv", "--v8-flags=--max-old-space-size=4096"]
+        );
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn scoped_permissions_warn_under_node_and_bun() {
+        for runtime in ["node", "bun"] {
+            let (_, warnings) = parse(json!({ "runtime": runtime })).unwrap();
+            assert_eq!(warnings.len(), 1);
+            assert!(warnings[0].contains(&format!("runs under {runtime} with full access")));
+        }
+
+        let (_, warnings) = parse(json!({ "runtime": "deno" })).unwrap();
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn settings_must_be_an_object() {
+        let err = parse(json!(["serverPath"])).unwrap_err();
+        assert_eq!(err, "`lsp.workman-lsp.settings` must be an object, found an array");
+    }
+
+    #[test]
+    fn unknown_keys_are_warnings() {
+        let (_, warnings) = parse(json!({
+            "serverRot": "/opt/workman",
+            "workman": { "format": { "enabled": false } },
+        }))
+        .unwrap();
+        assert_eq!(warnings, ["unknown setting `lsp.workman-lsp.settings.serverRot`"]);
+    }
+
+    #[test]
+    fn deep_merge_recurses_into_objects() {
+        let mut base = json!({
+            "workman": {
+                "format": { "enabled": true, "indentWidth": 2 },
+                "diagnostics": { "enabled": true },
+            },
+        });
+        deep_merge(
+            &mut base,
+            json!({
+                "workman": {
+                    "format": { "indentWidth": 4 },
+                    "inlayHints": { "enabled": false },
+                },
+            }),
+        );
+        assert_eq!(
+            base,
+            json!({
+                "workman": {
+                    "format": { "enabled": true, "indentWidth": 4 },
+                    "diagnostics": { "enabled": true },
+                    "inlayHints": { "enabled": false },
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn deep_merge_replaces_arrays_and_scalars() {
+        let mut base = json!({ "paths": ["a", "b"], "format": { "enabled": true } });
+        deep_merge(&mut base, json!({ "paths": ["c"], "format": null }));
+        assert_eq!(base, json!({ "paths": ["c"], "format": null }));
+
+        let mut base = json!({ "format": true });
+        deep_merge(&mut base, json!({ "format": { "enabled": false } }));
+        assert_eq!(base, json!({ "format": { "enabled": false } }));
+    }
+}
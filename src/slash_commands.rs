@@ -0,0 +1,72 @@
+use zed_extension_api::{
+    SlashCommandArgumentCompletion, SlashCommandOutput, SlashCommandOutputSection,
+};
+
+/// Joins labelled pieces of text into one output with a section for each.
+pub fn sections_output(
+    sections: impl IntoIterator<Item = (String, String)>,
+) -> SlashCommandOutput {
+    let mut output = SlashCommandOutput {
+        text: String::new(),
+        sections: Vec::new(),
+    };
+    for (label, text) in sections {
+        if !output.text.is_empty() {
+            output.text.push('\n');
+        }
+        let start = output.text.len();
+        output.text.push_str(&text);
+        output.sections.push(SlashCommandOutputSection {
+            range: (start..output.text.len()).into(),
+            label,
+        });
+    }
+    output
+}
+
+/// Completes a module name, running the command once one is picked.
+pub fn module_completions<'a>(
+    modules: impl IntoIterator<Item = &'a str>,
+    query: &str,
+) -> Vec<SlashCommandArgumentCompletion> {
+    modules
+        .into_iter()
+        .filter(|module| module.contains(query))
+        .map(|module| SlashCommandArgumentCompletion {
+            label: module.to_string(),
+            new_text: module.to_string(),
+            run_command: true,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sections_cover_their_own_text() {
+        let output = sections_output([
+            ("list".to_string(), "# list\n".to_string()),
+            ("option".to_string(), "# option\n".to_string()),
+        ]);
+        assert_eq!(output.text, "# list\n\n# option\n");
+        let ranges = output
+            .sections
+            .iter()
+            .map(|section| &output.text[section.range.start as usize..section.range.end as usize])
+            .collect::<Vec<_>>();
+        assert_eq!(ranges, ["# list\n", "# option\n"]);
+    }
+
+    #[test]
+    fn completes_matching_modules() {
+        let completions = module_completions(["list", "data/array", "option"], "a");
+        let labels = completions
+            .iter()
+            .map(|completion| completion.label.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(labels, ["data/array"]);
+        assert!(completions[0].run_command);
+    }
+}
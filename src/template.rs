@@ -0,0 +1,87 @@
+use zed_extension_api::Result;
+
+/// What a placeholder name stands for.
+pub enum Placeholder {
+    Value(String),
+    /// A placeholder that exists but has no value in this context, such as
+    /// `${denoConfig}` when the Deno config is disabled.
+    Unset,
+    Unknown,
+}
+
+/// Replaces every `${name}` in `input` with the value `lookup` returns for `name`.
+///
+/// A placeholder without a value is an error rather than being left in
+/// place, so typos surface before a broken command is spawned.
+pub fn expand(input: &str, lookup: impl Fn(&str) -> Placeholder) -> Result<String> {
+    let mut output = String::with_capacity(input.len());
+    let mut rest = input;
+    while let Some(start) = rest.find("${") {
+        output.push_str(&rest[..start]);
+        let placeholder = &rest[start + 2..];
+        let end = placeholder
+            .find('}')
+            .ok_or_else(|| format!("unterminated placeholder in {input:?}"))?;
+        let name = &placeholder[..end];
+        match lookup(name) {
+            Placeholder::Value(value) => output.push_str(&value),
+            Placeholder::Unset => {
+                return Err(format!("placeholder `${{{name}}}` has no value in {input:?}"))
+            }
+            Placeholder::Unknown => {
+                return Err(format!("unknown placeholder `${{{name}}}` in {input:?}"))
+            }
+        }
+        rest = &placeholder[end + 1..];
+    }
+    output.push_str(rest);
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lookup(name: &str) -> Placeholder {
+        match name {
+            "worktreeRoot" => Placeholder::Value("/work".to_string()),
+            "empty" => Placeholder::Value(String::new()),
+            "denoConfig" => Placeholder::Unset,
+            _ => Placeholder::Unknown,
+        }
+    }
+
+    #[test]
+    fn replaces_every_placeholder() {
+        assert_eq!(
+            expand("${worktreeRoot}/a:${worktreeRoot}/b${empty}", lookup).unwrap(),
+            "/work/a:/work/b"
+        );
+        assert_eq!(expand("no placeholders", lookup).unwrap(), "no placeholders");
+        assert_eq!(expand("$HOME/{x}$", lookup).unwrap(), "$HOME/{x}$");
+    }
+
+    #[test]
+    fn unterminated_placeholder_is_an_error() {
+        assert_eq!(
+            expand("${worktreeRoot", lookup).unwrap_err(),
+            "unterminated placeholder in \"${worktreeRoot\""
+        );
+    }
+
+    #[test]
+    fn unknown_placeholder_is_an_error() {
+        assert_eq!(
+            expand("${worktreRoot}/lsp", lookup).unwrap_err(),
+            "unknown placeholder `${worktreRoot}` in \"${worktreRoot}/lsp\""
+        );
+    }
+
+    #[test]
+    fn unset_placeholder_is_reported_separately() {
+        assert_eq!(
+            expand("--config=${denoConfig}", lookup).unwrap_err(),
+            "placeholder `${denoConfig}` has no value in \"--config=${denoConfig}\""
+        );
+    }
+}
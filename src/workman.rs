@@ -1,17 +1,361 @@
+mod docs;
+mod labels;
+mod settings;
+mod slash_commands;
+mod template;
+
+use std::collections::{BTreeMap, HashMap};
 use std::env;
+use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
 
 use zed_extension_api::{
     self as zed, serde_json, settings::LspSettings, LanguageServerId, Result,
 };
 
-struct WorkmanExtension;
+use crate::docs::ModuleDocs;
+use crate::settings::{
+    deep_merge, default_workspace_configuration, DenoConfig, PermissionMode, Runtime,
+    WorkmanSettings, SETTINGS_PATH,
+};
+use crate::template::Placeholder;
+
+const SERVER_REPO: &str = "mommysgoodpuppy/workman";
+const SERVER_ASSET: &str = "workman-lsp-server.tar.gz";
+const SERVER_DIR_PREFIX: &str = "workman-lsp-";
+const INSTALLED_MARKER: &str = ".installed";
+
+/// The Deno release downloaded when no Deno is installed.
+const DENO_VERSION: &str = "2.1.4";
+const DENO_DIR_PREFIX: &str = "deno-";
+
+/// Holds the isolated `DENO_DIR`s. Deliberately not prefixed `deno-`, which
+/// would make it look like a stale Deno download.
+const DENO_CACHE_DIR: &str = "cache";
+
+/// Worktree-relative Workman configuration files, lowest precedence first.
+const PROJECT_CONFIG_FILES: &[&str] = &["workman.json", ".workman/settings.json"];
+
+/// Where the standard library is assumed to live in a Workman checkout when
+/// `stdlibPath` is not set.
+const STDLIB_DIR: &str = "std";
+
+/// Environment variables the server may read in scoped permission mode.
+const SERVER_ENV_VARS: &[&str] = &["WORKMAN_ROOT", "HOME", "USERPROFILE", "NO_COLOR"];
+
+/// Worktree-relative locations where a Workman checkout is commonly vendored.
+const SERVER_SEARCH_PATHS: &[&str] = &[
+    "workman",
+    "tools/workman",
+    "vendor/workman",
+    "third_party/workman",
+    "deps/workman",
+];
+
+/// Module specifier schemes accepted for `serverSpecifier`.
+const SPECIFIER_SCHEMES: &[&str] = &["jsr:", "npm:", "https://", "http://", "file://"];
+
+/// Placeholders that only have a value once the server has been resolved,
+/// and not even then for every kind of server.
+const RESOLVED_PLACEHOLDERS: &[&str] = &["serverRoot", "serverPath", "denoConfig"];
+
+/// The name of a `deno compile`d server executable looked up on PATH.
+const SERVER_BINARY: &str = "workman-lsp";
+
+/// The indexed docs provider and the one package it indexes.
+const DOCS_PROVIDER: &str = "workman";
+const DOCS_PACKAGE: &str = "std";
+
+/// Prints every `.wm` file under the directory `ROOT` as a JSON object from
+/// relative path to contents. It runs under Deno, Node and Bun alike.
+const STDLIB_READER: &str = r#"import("node:fs").then(({ readdirSync, readFileSync }) => {
+  const root = ROOT;
+  const walk = (dir) =>
+    readdirSync(dir ? `${root}/${dir}` : root, { withFileTypes: true }).flatMap((entry) => {
+      const path = dir ? `${dir}/${entry.name}` : entry.name;
+      if (entry.isDirectory()) return walk(path);
+      return entry.name.endsWith(".wm") ? [[path, readFileSync(`${root}/${path}`, "utf8")]] : [];
+    });
+  console.log(JSON.stringify(Object.fromEntries(walk(""))));
+});"#;
+
+/// What the language server command runs.
+#[derive(Debug, Clone)]
+enum ServerEntry {
+    /// A local `server.ts`, run with Deno.
+    Script(String),
+    /// A `jsr:`, `npm:` or URL module specifier that Deno fetches itself.
+    Specifier(String),
+    /// A compiled server executable, launched directly.
+    Binary(String),
+}
+
+impl ServerEntry {
+    fn as_str(&self) -> &str {
+        match self {
+            Self::Script(path) | Self::Specifier(path) | Self::Binary(path) => path,
+        }
+    }
+
+    fn kind(&self) -> &'static str {
+        match self {
+            Self::Script(_) => "script",
+            Self::Specifier(_) => "specifier",
+            Self::Binary(_) => "binary",
+        }
+    }
+}
+
+/// Environment variables that influence resolution, besides the settings.
+const RESOLUTION_ENV_VARS: &[&str] = &[
+    "PATH",
+    "HOME",
+    "USERPROFILE",
+    "WORKMAN_ROOT",
+    "DENO_INSTALL",
+    "BUN_INSTALL",
+];
+
+/// The outcome of resolving the server and its runtime for one worktree.
+#[derive(Debug, Clone)]
+struct Resolution {
+    /// Settings and environment the resolution was made with.
+    fingerprint: String,
+    server: ServerEntry,
+    /// The file handed to the runtime, which differs from the server for Node.
+    entrypoint: String,
+    deno_config: Option<String>,
+    /// `None` when the server is a compiled executable.
+    runtime_binary: Option<String>,
+}
+
+impl Resolution {
+    /// Describes the resolution, including any downloads it relies on.
+    fn summary(&self) -> serde_json::Value {
+        let downloaded = [Some(self.server.as_str()), self.runtime_binary.as_deref()]
+            .into_iter()
+            .flatten()
+            .filter_map(WorkmanExtension::downloaded_version)
+            .map(|(name, version)| (name.to_string(), serde_json::Value::String(version)))
+            .collect::<serde_json::Map<_, _>>();
+        serde_json::json!({
+            "server": self.server.as_str(),
+            "serverKind": self.server.kind(),
+            "entrypoint": self.entrypoint,
+            "denoConfig": self.deno_config,
+            "runtimeBinary": self.runtime_binary,
+            "downloaded": downloaded,
+        })
+    }
+}
+
+/// What slash commands need to know about a started language server.
+struct LaunchedServer {
+    /// `stdlibPath`, or the [`STDLIB_DIR`] of the server checkout.
+    stdlib: Option<Stdlib>,
+}
+
+/// A Workman standard library and the means to read it.
+struct Stdlib {
+    dir: String,
+    /// A runtime on PATH that can evaluate a script, preferably the server's
+    /// own. Only runtimes on PATH are allowed to run scripts, by the
+    /// `process:exec` capabilities in `extension.toml`.
+    runtime: Option<Runtime>,
+    /// The environment the runtime is run with.
+    env: zed::EnvVars,
+    /// The docs of every module, read the first time they are needed.
+    docs: OnceLock<Vec<ModuleDocs>>,
+}
+
+impl Stdlib {
+    /// Returns the docs of every module, reading them on first use.
+    fn docs(&self) -> Result<&[ModuleDocs]> {
+        if let Some(docs) = self.docs.get() {
+            return Ok(docs);
+        }
+        let docs = self.read()?;
+        Ok(self.docs.get_or_init(|| docs))
+    }
+
+    /// Reads the doc comments of every module.
+    ///
+    /// The library may be outside the sandbox and the sandbox cannot list
+    /// directories anyway, so a runtime reads it with [`STDLIB_READER`].
+    fn read(&self) -> Result<Vec<ModuleDocs>> {
+        let dir = &self.dir;
+        let runtime = self.runtime.ok_or_else(|| {
+            "reading the Workman standard library needs deno, node or bun on PATH".to_string()
+        })?;
+        let root = serde_json::to_string(dir).map_err(|err| err.to_string())?;
+        let output = zed::process::Command::new(runtime.binary_name())
+            .args([runtime.eval_flag(), &STDLIB_READER.replace("ROOT", &root)])
+            .envs(self.env.iter().cloned())
+            .output()?;
+        if output.status != Some(0) {
+            return Err(format!(
+                "failed to read the Workman standard library at `{dir}`; set \
+                 {SETTINGS_PATH}.stdlibPath if it lives elsewhere: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        let sources = serde_json::from_slice::<BTreeMap<String, String>>(&output.stdout)
+            .map_err(|err| format!("failed to read the Workman standard library: {err}"))?;
+        if sources.is_empty() {
+            return Err(format!(
+                "no `.wm` files found in `{dir}`; set {SETTINGS_PATH}.stdlibPath to the \
+                 Workman standard library"
+            ));
+        }
+        Ok(sources
+            .iter()
+            .map(|(path, source)| ModuleDocs::parse(path.trim_end_matches(".wm"), source))
+            .collect())
+    }
+}
+
+#[derive(Default)]
+struct WorkmanExtension {
+    /// Resolutions by worktree ID, reused until the settings change or a
+    /// resolved file disappears.
+    resolutions: HashMap<u64, Resolution>,
+    /// Started language servers by worktree ID, which slash commands reuse.
+    servers: HashMap<u64, LaunchedServer>,
+    /// The fingerprint each worktree's settings warnings were printed for.
+    warned: HashMap<u64, String>,
+}
 
 impl WorkmanExtension {
-    fn resolve_deno_binary(
+    /// Reads and validates the worktree's settings.
+    ///
+    /// Zed asks for the command, the initialization options and the workspace
+    /// configuration separately, so warnings are only printed the first time
+    /// a worktree is seen with a given [`Self::fingerprint`].
+    fn settings(
+        &mut self,
+        worktree: &zed::Worktree,
+        lsp_settings: &LspSettings,
+        fingerprint: &str,
+    ) -> Result<WorkmanSettings> {
+        let (settings, warnings) = WorkmanSettings::from_value(lsp_settings.settings.clone())?;
+        if self.warned.get(&worktree.id()).map(String::as_str) != Some(fingerprint) {
+            for warning in warnings {
+                eprintln!("workman-lsp: {warning}");
+            }
+            self.warned.insert(worktree.id(), fingerprint.to_string());
+        }
+        Ok(settings)
+    }
+
+    /// Returns the cached resolution for the worktree, resolving afresh when
+    /// there is none or it is stale.
+    fn resolution(
+        &mut self,
+        language_server_id: &LanguageServerId,
+        worktree: &zed::Worktree,
+        fingerprint: String,
+        settings: &WorkmanSettings,
+    ) -> Result<Resolution> {
+        if let Some(cached) = self.resolutions.get(&worktree.id()) {
+            if !self.is_stale(worktree, cached, &fingerprint) {
+                return Ok(cached.clone());
+            }
+        }
+
+        self.resolutions.remove(&worktree.id());
+        let resolution = self.resolve(language_server_id, worktree, settings, fingerprint)?;
+        self.resolutions.insert(worktree.id(), resolution.clone());
+        Ok(resolution)
+    }
+
+    fn resolve(
+        &self,
+        language_server_id: &LanguageServerId,
+        worktree: &zed::Worktree,
+        settings: &WorkmanSettings,
+        fingerprint: String,
+    ) -> Result<Resolution> {
+        let server = self.resolve_server(language_server_id, worktree, settings)?;
+        let entrypoint = self
+            .runtime_entrypoint(worktree, settings.runtime, &server)
+            .map_err(|err| format!("{language_server_id}: {err}"))?;
+        let deno_config = match (settings.runtime, &server) {
+            (_, ServerEntry::Binary(_)) | (Runtime::Node | Runtime::Bun, _) => None,
+            (Runtime::Deno, _) => self.resolve_deno_config(worktree, settings, &server),
+        };
+        let runtime_binary = match server {
+            ServerEntry::Binary(_) => None,
+            ServerEntry::Script(_) | ServerEntry::Specifier(_) => {
+                Some(self.resolve_runtime_binary(language_server_id, worktree, settings)?)
+            }
+        };
+        Ok(Resolution {
+            fingerprint,
+            server,
+            entrypoint,
+            deno_config,
+            runtime_binary,
+        })
+    }
+
+    /// Captures everything a resolution depends on besides the filesystem.
+    fn fingerprint(&self, worktree: &zed::Worktree, lsp_settings: &LspSettings) -> String {
+        let shell_env = worktree.shell_env();
+        let env = RESOLUTION_ENV_VARS
+            .iter()
+            .map(|name| Self::lookup_env(&shell_env, name))
+            .collect::<Vec<_>>();
+        serde_json::json!([lsp_settings.binary, lsp_settings.settings, env]).to_string()
+    }
+
+    /// Whether a cached resolution can no longer be used: the settings or
+    /// environment changed, or a resolved file disappeared.
+    fn is_stale(&self, worktree: &zed::Worktree, cached: &Resolution, fingerprint: &str) -> bool {
+        if cached.fingerprint != fingerprint {
+            return true;
+        }
+
+        // Either the runtime or, for a compiled server, the server itself.
+        let executable = cached
+            .runtime_binary
+            .as_deref()
+            .unwrap_or(&cached.entrypoint);
+        if worktree.which(executable).is_none() {
+            return true;
+        }
+
+        let mut files = Vec::new();
+        if let ServerEntry::Script(_) = cached.server {
+            files.push(cached.entrypoint.as_str());
+        }
+        files.extend(cached.deno_config.as_deref());
+        files
+            .into_iter()
+            .any(|file| matches!(self.probe_file(worktree, Path::new(file)), Some(Err(_))))
+    }
+
+    /// Recognizes a path inside a download made by the extension, returning
+    /// what was downloaded and its version.
+    fn downloaded_version(path: &str) -> Option<(&'static str, String)> {
+        let work_dir = env::current_dir().ok()?;
+        let relative = Path::new(path).strip_prefix(work_dir).ok()?;
+        let dir = relative.components().next()?.as_os_str().to_str()?;
+        if let Some(version) = dir.strip_prefix(SERVER_DIR_PREFIX) {
+            Some(("server", version.to_string()))
+        } else {
+            dir.strip_prefix(DENO_DIR_PREFIX)
+                .map(|version| ("deno", version.to_string()))
+        }
+    }
+
+    /// Finds the executable for the configured runtime, which
+    /// `lsp.workman-lsp.binary.path` overrides for every runtime.
+    fn resolve_runtime_binary(
         &self,
         language_server_id: &LanguageServerId,
         worktree: &zed::Worktree,
+        settings: &WorkmanSettings,
     ) -> Result<String> {
         if let Ok(lsp_settings) = LspSettings::for_worktree("workman-lsp", worktree) {
             if let Some(binary) = lsp_settings.binary {
@@ -21,83 +365,1229 @@ impl WorkmanExtension {
             }
         }
 
-        worktree
-            .which("deno")
-            .ok_or_else(|| format!("{language_server_id}: could not find deno on PATH"))
+        match settings.runtime {
+            Runtime::Deno => self.resolve_deno_binary(language_server_id, worktree, settings),
+            Runtime::Node | Runtime::Bun => {
+                self.resolve_js_runtime_binary(language_server_id, worktree, settings.runtime)
+            }
+        }
     }
 
-    fn resolve_server_paths(&self, worktree: &zed::Worktree) -> Result<(String, String)> {
-        if let Ok(lsp_settings) = LspSettings::for_worktree("workman-lsp", worktree) {
-            if let Some(settings) = lsp_settings.settings {
-                if let Some(server_path) = settings
-                    .get("serverPath")
-                    .and_then(|value| value.as_str())
-                {
-                    let server_path = PathBuf::from(server_path);
-                    let deno_config = settings
-                        .get("denoConfig")
-                        .and_then(|value| value.as_str())
-                        .map(PathBuf::from)
-                        .unwrap_or_else(|| {
-                            server_path
-                                .parent()
-                                .and_then(Path::parent)
-                                .map(|parent| parent.join("deno.json"))
-                                .unwrap_or_else(|| PathBuf::from("deno.json"))
-                        });
-                    return Ok((
-                        deno_config.to_string_lossy().to_string(),
-                        server_path.to_string_lossy().to_string(),
-                    ));
+    /// Finds Node or Bun.
+    ///
+    /// Node falls back to the copy Zed itself manages, Bun to its default
+    /// install location.
+    fn resolve_js_runtime_binary(
+        &self,
+        language_server_id: &LanguageServerId,
+        worktree: &zed::Worktree,
+        runtime: Runtime,
+    ) -> Result<String> {
+        let name = runtime.binary_name();
+        let mut rejected = vec!["lsp.workman-lsp.binary.path: not set".to_string()];
+
+        if let Some(path) = worktree.which(name) {
+            return Ok(path);
+        }
+        rejected.push(format!("PATH: no `{name}` executable found"));
+
+        match runtime {
+            Runtime::Node => match zed::node_binary_path() {
+                Ok(path) => return Ok(path),
+                Err(reason) => rejected.push(format!("Zed's Node.js: {reason}")),
+            },
+            Runtime::Bun => {
+                let install_dir = self.env_var(worktree, "BUN_INSTALL").or_else(|| {
+                    self.env_var(worktree, "HOME")
+                        .map(|home| Path::new(&home).join(".bun").to_string_lossy().to_string())
+                });
+                match install_dir {
+                    Some(install_dir) => {
+                        let candidate = Path::new(&install_dir).join("bin").join(name);
+                        match worktree.which(&candidate.to_string_lossy()) {
+                            Some(path) => return Ok(path),
+                            None => rejected.push(format!(
+                                "BUN_INSTALL: no executable at `{}`",
+                                candidate.display()
+                            )),
+                        }
+                    }
+                    None => rejected.push("BUN_INSTALL: not set".to_string()),
                 }
+            }
+            Runtime::Deno => {}
+        }
 
-                if let Some(server_root) = settings
-                    .get("serverRoot")
-                    .and_then(|value| value.as_str())
-                {
-                    return Ok(self.paths_from_root(PathBuf::from(server_root)));
+        Err(Self::report(
+            &format!("{language_server_id}: could not find {name}"),
+            &rejected,
+        ))
+    }
+
+    /// Picks the file the runtime executes for a resolved server.
+    ///
+    /// Deno and Bun run TypeScript directly. Node needs the built JavaScript,
+    /// so for a `src/server.ts` the `dist/server.js` (or `.mjs`) next to `src/`
+    /// is used instead. Remote modules can only be run by Deno.
+    fn runtime_entrypoint(
+        &self,
+        worktree: &zed::Worktree,
+        runtime: Runtime,
+        server: &ServerEntry,
+    ) -> Result<String> {
+        let server_path = match (runtime, server) {
+            (Runtime::Deno, _) | (_, ServerEntry::Binary(_)) => {
+                return Ok(server.as_str().to_string())
+            }
+            (_, ServerEntry::Specifier(specifier)) => {
+                return Err(format!(
+                    "{specifier:?} is a module specifier, which only the deno runtime can run"
+                ))
+            }
+            (Runtime::Bun, ServerEntry::Script(path)) => return Ok(path.clone()),
+            (Runtime::Node, ServerEntry::Script(path)) => Path::new(path),
+        };
+
+        if matches!(
+            server_path.extension().and_then(|ext| ext.to_str()),
+            Some("js" | "mjs" | "cjs")
+        ) {
+            return Ok(server_path.to_string_lossy().to_string());
+        }
+
+        let server_dir = server_path
+            .parent()
+            .and_then(Path::parent)
+            .ok_or_else(|| format!("node cannot run `{}`", server_path.display()))?;
+        let mut unknown = false;
+        for name in ["server.js", "server.mjs"] {
+            let built = server_dir.join("dist").join(name);
+            match self.probe_file(worktree, &built) {
+                Some(Ok(())) => return Ok(built.to_string_lossy().to_string()),
+                Some(Err(_)) => {}
+                None => unknown = true,
+            }
+        }
+        if unknown {
+            return Ok(server_dir.join("dist").join("server.js").to_string_lossy().to_string());
+        }
+
+        Err(format!(
+            "node cannot run `{}` directly; build the server to `{}` or point \
+             {SETTINGS_PATH}.serverPath at a JavaScript entrypoint",
+            server_path.display(),
+            server_dir.join("dist").join("server.js").display(),
+        ))
+    }
+
+    /// Finds a Deno executable, downloading a private copy as a last resort.
+    fn resolve_deno_binary(
+        &self,
+        language_server_id: &LanguageServerId,
+        worktree: &zed::Worktree,
+        settings: &WorkmanSettings,
+    ) -> Result<String> {
+        let mut rejected = vec!["lsp.workman-lsp.binary.path: not set".to_string()];
+
+        if let Some(path) = worktree.which("deno") {
+            return Ok(path);
+        }
+        rejected.push("PATH: no `deno` executable found".to_string());
+
+        let (os, _) = zed::current_platform();
+        let binary_name = match os {
+            zed::Os::Windows => "deno.exe",
+            zed::Os::Mac | zed::Os::Linux => "deno",
+        };
+        let well_known = [
+            ("DENO_INSTALL", self.env_var(worktree, "DENO_INSTALL")),
+            (
+                "~/.deno",
+                self.env_var(worktree, "HOME")
+                    .or_else(|| self.env_var(worktree, "USERPROFILE"))
+                    .map(|home| Path::new(&home).join(".deno").to_string_lossy().to_string()),
+            ),
+        ];
+        for (source, install_dir) in well_known {
+            let Some(install_dir) = install_dir else {
+                rejected.push(format!("{source}: not set"));
+                continue;
+            };
+            let candidate = Path::new(&install_dir).join("bin").join(binary_name);
+            // `which` checks a path containing a separator directly, which
+            // lets us see outside the sandbox.
+            match worktree.which(&candidate.to_string_lossy()) {
+                Some(path) => return Ok(path),
+                None => rejected.push(format!(
+                    "{source}: no executable at `{}`",
+                    candidate.display()
+                )),
+            }
+        }
+
+        if settings.auto_download {
+            match self.download_deno(language_server_id, binary_name) {
+                Ok(path) => return Ok(path),
+                Err(reason) => rejected.push(format!("deno download: {reason}")),
+            }
+        } else {
+            rejected.push(format!("deno download: disabled by {SETTINGS_PATH}.autoDownload"));
+        }
+
+        Err(Self::report(
+            &format!("{language_server_id}: could not find deno"),
+            &rejected,
+        ))
+    }
+
+    /// Installs the pinned [`DENO_VERSION`] into the extension work directory.
+    fn download_deno(
+        &self,
+        language_server_id: &LanguageServerId,
+        binary_name: &str,
+    ) -> Result<String> {
+        let (os, arch) = zed::current_platform();
+        let target = match (os, arch) {
+            (zed::Os::Mac, zed::Architecture::Aarch64) => "aarch64-apple-darwin",
+            (zed::Os::Mac, zed::Architecture::X8664) => "x86_64-apple-darwin",
+            (zed::Os::Linux, zed::Architecture::Aarch64) => "aarch64-unknown-linux-gnu",
+            (zed::Os::Linux, zed::Architecture::X8664) => "x86_64-unknown-linux-gnu",
+            (zed::Os::Windows, zed::Architecture::X8664) => "x86_64-pc-windows-msvc",
+            (os, arch) => return Err(format!("no Deno release for {os:?} {arch:?}")),
+        };
+
+        let deno_dir = format!("{DENO_DIR_PREFIX}{DENO_VERSION}");
+        let binary_path = Path::new(&deno_dir).join(binary_name);
+        if !fs::metadata(&binary_path).is_ok_and(|stat| stat.is_file()) {
+            zed::set_language_server_installation_status(
+                language_server_id,
+                &zed::LanguageServerInstallationStatus::Downloading,
+            );
+
+            let url = format!(
+                "https://github.com/denoland/deno/releases/download/v{DENO_VERSION}/deno-{target}.zip"
+            );
+            if let Err(err) = zed::download_file(&url, &deno_dir, zed::DownloadedFileType::Zip) {
+                zed::set_language_server_installation_status(
+                    language_server_id,
+                    &zed::LanguageServerInstallationStatus::Failed(err.clone()),
+                );
+                return Err(format!("failed to download {url}: {err}"));
+            }
+            zed::make_file_executable(&binary_path.to_string_lossy())?;
+
+            if let Ok(entries) = fs::read_dir(".") {
+                for entry in entries.flatten() {
+                    if let Some(name) = entry.file_name().to_str() {
+                        if name.starts_with(DENO_DIR_PREFIX) && name != deno_dir {
+                            fs::remove_dir_all(entry.path()).ok();
+                        }
+                    }
                 }
             }
+
+            zed::set_language_server_installation_status(
+                language_server_id,
+                &zed::LanguageServerInstallationStatus::None,
+            );
+        }
+
+        Ok(env::current_dir()
+            .map_err(|err| format!("failed to get extension work directory: {err}"))?
+            .join(binary_path)
+            .to_string_lossy()
+            .to_string())
+    }
+
+    /// Walks the server resolution chain, returning the first usable candidate.
+    ///
+    /// When nothing is usable the error lists every candidate in order along
+    /// with the reason it was rejected.
+    fn resolve_server(
+        &self,
+        language_server_id: &LanguageServerId,
+        worktree: &zed::Worktree,
+        settings: &WorkmanSettings,
+    ) -> Result<ServerEntry> {
+        let mut rejected = Vec::new();
+
+        match &settings.server_binary {
+            // `which` checks a path containing a separator directly and looks
+            // a bare name up on PATH.
+            Some(server_binary) => match worktree.which(server_binary) {
+                Some(path) => return Ok(ServerEntry::Binary(path)),
+                None => rejected.push(format!(
+                    "{SETTINGS_PATH}.serverBinary: no executable `{server_binary}` found"
+                )),
+            },
+            None => rejected.push(format!("{SETTINGS_PATH}.serverBinary: not set")),
+        }
+
+        match &settings.server_path {
+            Some(server_path) => match self.check_file(worktree, Path::new(server_path)) {
+                Ok(()) => return Ok(ServerEntry::Script(server_path.clone())),
+                Err(reason) => rejected.push(format!("{SETTINGS_PATH}.serverPath: {reason}")),
+            },
+            None => rejected.push(format!("{SETTINGS_PATH}.serverPath: not set")),
+        }
+
+        match &settings.server_root {
+            Some(server_root) => match self.server_from_root(worktree, PathBuf::from(server_root)) {
+                Ok(path) => return Ok(ServerEntry::Script(path)),
+                Err(reason) => rejected.push(format!("{SETTINGS_PATH}.serverRoot: {reason}")),
+            },
+            None => rejected.push(format!("{SETTINGS_PATH}.serverRoot: not set")),
+        }
+
+        match &settings.server_specifier {
+            Some(specifier)
+                if SPECIFIER_SCHEMES
+                    .iter()
+                    .any(|scheme| specifier.starts_with(scheme)) =>
+            {
+                return Ok(ServerEntry::Specifier(specifier.clone()))
+            }
+            Some(specifier) => rejected.push(format!(
+                "{SETTINGS_PATH}.serverSpecifier: {specifier:?} does not start with one of {}",
+                SPECIFIER_SCHEMES.join(", ")
+            )),
+            None => rejected.push(format!("{SETTINGS_PATH}.serverSpecifier: not set")),
+        }
+
+        match self.env_var(worktree, "WORKMAN_ROOT") {
+            Some(server_root) => match self
+                .absolute_path(worktree, &server_root)
+                .and_then(|server_root| self.server_from_root(worktree, PathBuf::from(server_root)))
+            {
+                Ok(path) => return Ok(ServerEntry::Script(path)),
+                Err(reason) => rejected.push(format!("WORKMAN_ROOT: {reason}")),
+            },
+            None => rejected
+                .push("WORKMAN_ROOT: not set in the shell or extension environment".to_string()),
+        }
+
+        match self.discover_server(worktree, settings) {
+            Ok(path) => return Ok(ServerEntry::Script(path)),
+            Err(reason) => rejected.push(format!("worktree search: {reason}")),
+        }
+
+        match worktree.which(SERVER_BINARY) {
+            Some(path) => return Ok(ServerEntry::Binary(path)),
+            None => rejected.push(format!("PATH: no `{SERVER_BINARY}` executable found")),
+        }
+
+        if settings.auto_download {
+            match self.server_from_download(language_server_id, settings) {
+                Ok(path) => return Ok(ServerEntry::Script(path)),
+                Err(reason) => rejected.push(format!("release download: {reason}")),
+            }
+        } else {
+            rejected.push(format!(
+                "release download: disabled by {SETTINGS_PATH}.autoDownload"
+            ));
         }
 
-        if let Ok(server_root) = env::var("WORKMAN_ROOT") {
-            return Ok(self.paths_from_root(PathBuf::from(server_root)));
+        Err(Self::report(
+            &format!("{language_server_id}: could not locate the Workman language server"),
+            &rejected,
+        ))
+    }
+
+    /// Formats a resolution failure as a headline followed by numbered attempts.
+    fn report(headline: &str, rejected: &[String]) -> String {
+        let mut report = format!("{headline}. Tried:");
+        for (ix, reason) in rejected.iter().enumerate() {
+            report.push_str(&format!("\n  {}. {reason}", ix + 1));
         }
+        report
+    }
 
-        self.paths_from_worktree(worktree)
+    /// Checks that a server file exists where we are able to look.
+    ///
+    /// The sandbox cannot see outside the worktree and the extension work
+    /// directory, so other paths are trusted as configured.
+    fn check_file(&self, worktree: &zed::Worktree, path: &Path) -> Result<()> {
+        self.probe_file(worktree, path).unwrap_or(Ok(()))
     }
 
-    fn paths_from_root(&self, root: PathBuf) -> (String, String) {
-        let deno_config = root.join("lsp").join("server").join("deno.json");
-        let server_path = root
-            .join("lsp")
-            .join("server")
-            .join("src")
-            .join("server.ts");
-        (
-            deno_config.to_string_lossy().to_string(),
-            server_path.to_string_lossy().to_string(),
-        )
+    /// Probes for a file, returning `None` when its existence cannot be known.
+    ///
+    /// Files in the worktree are read through the worktree and files in the
+    /// extension work directory through the filesystem.
+    fn probe_file(&self, worktree: &zed::Worktree, path: &Path) -> Option<Result<()>> {
+        if let Ok(relative) = path.strip_prefix(worktree.root_path()) {
+            return Some(
+                worktree
+                    .read_text_file(&relative.to_string_lossy())
+                    .map(|_| ())
+                    .map_err(|err| format!("`{}` is not readable: {err}", path.display())),
+            );
+        }
+
+        let work_dir = env::current_dir().ok()?;
+        path.starts_with(work_dir).then(|| {
+            fs::metadata(path)
+                .map(|_| ())
+                .map_err(|err| format!("`{}` is not readable: {err}", path.display()))
+        })
+    }
+
+    /// Picks the Deno config for a resolved server.
+    ///
+    /// Unless `denoConfig` names a file or disables the config, this is the
+    /// `deno.json` or `deno.jsonc` next to a local script's `src/` directory.
+    /// When neither exists, or the server is a remote module, `--config` is
+    /// left out so Deno does not fail on it.
+    ///
+    /// A checkout outside the worktree, such as one found through `serverRoot`
+    /// or `WORKMAN_ROOT`, cannot be looked into from the sandbox. `deno.json`
+    /// is then assumed and a warning says so, since a checkout that only has
+    /// a `deno.jsonc` needs `denoConfig` set to start.
+    fn resolve_deno_config(
+        &self,
+        worktree: &zed::Worktree,
+        settings: &WorkmanSettings,
+        server: &ServerEntry,
+    ) -> Option<String> {
+        let server_path = match (&settings.deno_config, server) {
+            (_, ServerEntry::Binary(_)) => return None,
+            (DenoConfig::Path(path), _) => return Some(path.clone()),
+            (DenoConfig::Disabled, _) | (DenoConfig::Auto, ServerEntry::Specifier(_)) => {
+                return None
+            }
+            (DenoConfig::Auto, ServerEntry::Script(server_path)) => server_path,
+        };
+
+        let server_dir = Path::new(server_path).parent()?.parent()?;
+        let mut unknown = false;
+        for name in ["deno.json", "deno.jsonc"] {
+            let config = server_dir.join(name);
+            match self.probe_file(worktree, &config) {
+                Some(Ok(())) => return Some(config.to_string_lossy().to_string()),
+                Some(Err(_)) => {}
+                None => unknown = true,
+            }
+        }
+
+        // Outside the sandbox we cannot tell, so assume the checkout layout.
+        unknown.then(|| {
+            let config = server_dir.join("deno.json");
+            eprintln!(
+                "workman-lsp: cannot check for deno.json or deno.jsonc in `{}` from the \
+                 extension sandbox, assuming `{}`; set {SETTINGS_PATH}.denoConfig to the \
+                 config file, or to false, if that is wrong",
+                server_dir.display(),
+                config.display(),
+            );
+            config.to_string_lossy().to_string()
+        })
     }
 
-    fn paths_from_worktree(&self, worktree: &zed::Worktree) -> Result<(String, String)> {
-        let (deno_config, server_path) = self.paths_from_root(PathBuf::from(worktree.root_path()));
-        if worktree
-            .read_text_file("lsp/server/src/server.ts")
-            .is_err()
+    /// Builds runtime arguments for the resolved server.
+    ///
+    /// `runtimeArgs` go before the script so the runtime interprets them,
+    /// `serverArgs` after it so they reach the server. Both are ignored when
+    /// `binary.arguments` replaces the whole argument list. Permissions, the
+    /// Deno config, the lockfile and `cachedOnly` only apply to Deno.
+    fn server_arguments(
+        &self,
+        worktree: &zed::Worktree,
+        settings: &WorkmanSettings,
+        server_root: Option<&str>,
+        stdlib: Option<&str>,
+        deno_config: Option<String>,
+        entrypoint: &str,
+    ) -> Vec<String> {
+        let mut args = Vec::new();
+        match settings.runtime {
+            Runtime::Deno => {
+                args.push("run".to_string());
+                args.extend(self.permission_flags(
+                    worktree,
+                    settings,
+                    server_root,
+                    stdlib,
+                    deno_config.as_deref(),
+                ));
+                if let Some(deno_config) = deno_config {
+                    args.push("--config".to_string());
+                    args.push(deno_config);
+                }
+                if let Some(lockfile) = &settings.lockfile {
+                    args.push(format!("--lock={lockfile}"));
+                }
+                if settings.cached_only {
+                    args.push("--cached-only".to_string());
+                }
+            }
+            Runtime::Node => {}
+            Runtime::Bun => args.push("run".to_string()),
+        }
+        args.extend(settings.runtime_args.iter().cloned());
+        args.push(entrypoint.to_string());
+        args.extend(settings.server_args.iter().cloned());
+        args
+    }
+
+    /// Builds Deno permission flags according to `permissions.mode`.
+    ///
+    /// In scoped mode the server may read the worktree, its own checkout, the
+    /// standard library and its Deno config, and read the [`SERVER_ENV_VARS`].
+    /// Anything further, including network access and subprocesses, has to be
+    /// listed in `permissions`. Node and Bun cannot be restricted this way,
+    /// which the settings warn about.
+    fn permission_flags(
+        &self,
+        worktree: &zed::Worktree,
+        settings: &WorkmanSettings,
+        server_root: Option<&str>,
+        stdlib: Option<&str>,
+        deno_config: Option<&str>,
+    ) -> Vec<String> {
+        let permissions = &settings.permissions;
+        if permissions.mode == PermissionMode::AllowAll {
+            return vec!["--allow-all".to_string()];
+        }
+
+        let mut read = vec![worktree.root_path()];
+        read.extend(server_root.map(str::to_string));
+        read.extend(stdlib.map(str::to_string));
+        let lockfile_dir = settings
+            .lockfile
+            .as_deref()
+            .and_then(|lockfile| Path::new(lockfile).parent());
+        if let Some(lockfile_dir) = lockfile_dir {
+            read.push(lockfile_dir.to_string_lossy().to_string());
+        }
+        if let Some(config_dir) = deno_config.and_then(|config| Path::new(config).parent()) {
+            read.push(config_dir.to_string_lossy().to_string());
+        }
+        read.extend(permissions.read.iter().cloned());
+        read.retain(|path| !path.is_empty());
+        read.dedup();
+
+        let mut env = SERVER_ENV_VARS
+            .iter()
+            .map(|var| var.to_string())
+            .collect::<Vec<_>>();
+        env.extend(permissions.env.iter().cloned());
+        env.extend(
+            settings
+                .env
+                .iter()
+                .filter(|(_, value)| value.is_some())
+                .map(|(name, _)| name.clone()),
+        );
+
+        let mut flags = vec![
+            format!("--allow-read={}", read.join(",")),
+            format!("--allow-env={}", env.join(",")),
+        ];
+        if !permissions.net.is_empty() {
+            flags.push(format!("--allow-net={}", permissions.net.join(",")));
+        }
+        if !permissions.run.is_empty() {
+            flags.push(format!("--allow-run={}", permissions.run.join(",")));
+        }
+        flags
+    }
+
+    /// Expands placeholders in the path settings and makes them absolute.
+    ///
+    /// `serverBinary` is left alone when it is a bare command name rather
+    /// than a path.
+    ///
+    /// Each setting may refer to the ones expanded before it: `serverRoot`
+    /// first, then `serverPath`, then `lockfile`, `stdlibPath` and `denoConfig`.
+    /// `serverSpecifier` is expanded too but, not being a path, left as is.
+    fn expand_settings(
+        &self,
+        worktree: &zed::Worktree,
+        mut settings: WorkmanSettings,
+    ) -> Result<WorkmanSettings> {
+        let expand_key = |key: &str, value: &Option<String>, known: &[(&str, &str)]| {
+            value
+                .as_deref()
+                .map(|value| {
+                    let value = self.expand(worktree, value, known)?;
+                    self.absolute_path(worktree, &value)
+                })
+                .transpose()
+                .map_err(|err| format!("{SETTINGS_PATH}.{key}: {err}"))
+        };
+
+        settings.server_root = expand_key("serverRoot", &settings.server_root, &[])?;
+        let server_root = settings.server_root.clone();
+        let mut known = Vec::new();
+        if let Some(server_root) = &server_root {
+            known.push(("serverRoot", server_root.as_str()));
+        }
+        settings.server_path = expand_key("serverPath", &settings.server_path, &known)?;
+        let server_path = settings.server_path.clone();
+        if let Some(server_path) = &server_path {
+            known.push(("serverPath", server_path.as_str()));
+        }
+        // A bare command name is looked up on PATH, so only paths are resolved.
+        settings.server_binary = settings
+            .server_binary
+            .as_deref()
+            .map(|value| {
+                let value = self.expand(worktree, value, &[])?;
+                if value.contains(['/', '\\']) || value.starts_with(['~', '.']) {
+                    self.absolute_path(worktree, &value)
+                } else {
+                    Ok(value)
+                }
+            })
+            .transpose()
+            .map_err(|err| format!("{SETTINGS_PATH}.serverBinary: {err}"))?;
+        settings.lockfile = expand_key("lockfile", &settings.lockfile, &known)?;
+        settings.stdlib_path = expand_key("stdlibPath", &settings.stdlib_path, &known)?;
+        settings.server_specifier = settings
+            .server_specifier
+            .as_deref()
+            .map(|specifier| self.expand(worktree, specifier, &[]))
+            .transpose()
+            .map_err(|err| format!("{SETTINGS_PATH}.serverSpecifier: {err}"))?;
+        if let DenoConfig::Path(deno_config) = &settings.deno_config {
+            let deno_config = expand_key("denoConfig", &Some(deno_config.clone()), &known)?;
+            settings.deno_config = DenoConfig::Path(deno_config.unwrap_or_default());
+        }
+        Ok(settings)
+    }
+
+    /// Resolves a configured path to an absolute one for the worktree.
+    fn absolute_path(&self, worktree: &zed::Worktree, path: &str) -> Result<String> {
+        let home = self
+            .env_var(worktree, "HOME")
+            .or_else(|| self.env_var(worktree, "USERPROFILE"));
+        Self::resolve_path(&worktree.root_path(), home.as_deref(), path)
+    }
+
+    /// Resolves `path` against the worktree `root`.
+    ///
+    /// A leading `~` or `$HOME` is replaced with `home` and other relative
+    /// paths are taken relative to `root`. `.` and `..` components are then
+    /// removed lexically.
+    ///
+    /// The extension runs under WASI, whose paths follow Unix rules whatever
+    /// the host, so Windows paths are recognized by hand: a path with a drive
+    /// letter or a UNC prefix is absolute, and is normalized with `/`
+    /// separators, which Windows accepts and [`Path`] can take apart.
+    fn resolve_path(root: &str, home: Option<&str>, path: &str) -> Result<String> {
+        let home_relative = ["~", "$HOME"].iter().find_map(|prefix| {
+            let rest = path.strip_prefix(prefix)?;
+            (rest.is_empty() || rest.starts_with(['/', '\\'])).then_some(rest)
+        });
+
+        let path = match home_relative {
+            Some(rest) => {
+                let home = home.ok_or_else(|| format!("cannot expand {path:?}: HOME is not set"))?;
+                format!("{home}/{rest}")
+            }
+            None if path.starts_with('/') || Self::windows_prefix(path).is_some() => {
+                path.to_string()
+            }
+            None => format!("{root}/{path}"),
+        };
+
+        let (prefix, rest, separators) = match Self::windows_prefix(&path) {
+            Some(len) => (path[..len].replace('\\', "/"), &path[len..], &['/', '\\'][..]),
+            None => (String::new(), path.as_str(), &['/'][..]),
+        };
+        let mut normalized = Vec::new();
+        for component in rest.split(separators) {
+            match component {
+                "" | "." => {}
+                ".." => {
+                    normalized.pop();
+                }
+                component => normalized.push(component),
+            }
+        }
+        if normalized.is_empty() && prefix.starts_with("//") {
+            // A UNC share is a root of its own.
+            return Ok(prefix);
+        }
+        Ok(format!("{prefix}/{}", normalized.join("/")))
+    }
+
+    /// The length of the drive (`C:`) or UNC (`\\server\share`) prefix
+    /// of a Windows path.
+    fn windows_prefix(path: &str) -> Option<usize> {
+        let bytes = path.as_bytes();
+        if bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':' {
+            return Some(2);
+        }
+        let unc = path.strip_prefix("\\\\")?;
+        let mut parts = unc.splitn(3, ['/', '\\']);
+        let server = parts.next().filter(|server| !server.is_empty())?;
+        let share = parts.next().filter(|share| !share.is_empty())?;
+        Some(2 + server.len() + 1 + share.len())
+    }
+
+    /// Expands `${worktreeRoot}`, `${extensionWorkDir}`, `${env:NAME}` and any
+    /// of the `known` values in `input`.
+    ///
+    /// Environment variables are looked up with [`Self::env_var`] and expand
+    /// to an empty string when unset. [`RESOLVED_PLACEHOLDERS`] missing from
+    /// `known` are reported as having no value rather than as unknown.
+    fn expand(
+        &self,
+        worktree: &zed::Worktree,
+        input: &str,
+        known: &[(&str, &str)],
+    ) -> Result<String> {
+        template::expand(input, |name| {
+            if let Some(var) = name.strip_prefix("env:") {
+                return Placeholder::Value(self.env_var(worktree, var).unwrap_or_default());
+            }
+            let value = match name {
+                "worktreeRoot" => Some(worktree.root_path()),
+                "extensionWorkDir" => env::current_dir()
+                    .ok()
+                    .map(|dir| dir.to_string_lossy().to_string()),
+                _ => known
+                    .iter()
+                    .find(|(key, _)| *key == name)
+                    .map(|(_, value)| value.to_string()),
+            };
+            match value {
+                Some(value) => Placeholder::Value(value),
+                None if RESOLVED_PLACEHOLDERS.contains(&name) => Placeholder::Unset,
+                None => Placeholder::Unknown,
+            }
+        })
+    }
+
+    /// Derives the checkout root from a resolved server script.
+    ///
+    /// For a checkout this is the directory containing `lsp/`; for a bundle
+    /// or any other layout it is the directory containing the script's `src/`.
+    /// Remote modules and compiled executables have no root.
+    fn server_root(server: &ServerEntry) -> Option<String> {
+        let ServerEntry::Script(server_path) = server else {
+            return None;
+        };
+        let server_path = Path::new(server_path);
+        let levels = if server_path.ends_with("lsp/server/src/server.ts") {
+            4
+        } else {
+            2
+        };
+        server_path
+            .ancestors()
+            .nth(levels)
+            .map(|root| root.to_string_lossy().to_string())
+    }
+
+    /// Picks the standard library: `stdlibPath` if set, and otherwise the
+    /// [`STDLIB_DIR`] of the checkout.
+    ///
+    /// The sandbox can only tell whether that directory exists inside the
+    /// extension work directory, where it rules out a downloaded bundle, which
+    /// has none. Elsewhere the directory is assumed to exist, and reading it
+    /// says so when it does not.
+    fn stdlib(
+        &self,
+        worktree: &zed::Worktree,
+        settings: &WorkmanSettings,
+        server_root: Option<&str>,
+        env: zed::EnvVars,
+    ) -> Option<Stdlib> {
+        let dir = match &settings.stdlib_path {
+            Some(stdlib_path) => stdlib_path.clone(),
+            None => {
+                let dir = Path::new(server_root?).join(STDLIB_DIR);
+                let work_dir = env::current_dir().ok()?;
+                if dir.starts_with(work_dir) && !fs::metadata(&dir).is_ok_and(|stat| stat.is_dir())
+                {
+                    return None;
+                }
+                dir.to_string_lossy().to_string()
+            }
+        };
+        let runtime = [settings.runtime, Runtime::Deno, Runtime::Node, Runtime::Bun]
+            .into_iter()
+            .find(|runtime| worktree.which(runtime.binary_name()).is_some());
+        Some(Stdlib {
+            dir,
+            runtime,
+            env,
+            docs: OnceLock::new(),
+        })
+    }
+
+    /// Finds the worktree's standard library without starting the server.
+    ///
+    /// The checkout is looked for through `serverPath`, `serverRoot`,
+    /// `WORKMAN_ROOT` and the worktree search, like the server resolution
+    /// does, but nothing is downloaded: a downloaded bundle has no standard
+    /// library.
+    fn discover_stdlib(&self, worktree: &zed::Worktree) -> Result<Stdlib> {
+        let lsp_settings = LspSettings::for_worktree("workman-lsp", worktree).unwrap_or_default();
+        // Warnings are printed when the server starts.
+        let (settings, _) = WorkmanSettings::from_value(lsp_settings.settings)?;
+        let settings = self.expand_settings(worktree, settings)?;
+        let server_root = settings
+            .server_path
+            .clone()
+            .and_then(|path| Self::server_root(&ServerEntry::Script(path)))
+            .or_else(|| settings.server_root.clone())
+            .or_else(|| {
+                let root = self.env_var(worktree, "WORKMAN_ROOT")?;
+                self.absolute_path(worktree, &root).ok()
+            })
+            .or_else(|| {
+                let path = self.discover_server(worktree, &settings).ok()?;
+                Self::server_root(&ServerEntry::Script(path))
+            });
+        let env = self.server_env(worktree, &settings, None)?;
+        self.stdlib(worktree, &settings, server_root.as_deref(), env)
+            .ok_or_else(|| {
+                format!(
+                    "no Workman standard library found; set {SETTINGS_PATH}.stdlibPath or \
+                     {SETTINGS_PATH}.serverRoot"
+                )
+            })
+    }
+
+    /// Prepares the extension-owned `DENO_DIR` when `isolatedDenoDir` is on.
+    ///
+    /// Each `generation` gets its own directory under [`DENO_CACHE_DIR`], and
+    /// the others are deleted, so changing `generation` wipes the cache and
+    /// starts a new one. A fresh directory is unpacked from `seedUrl` when one
+    /// is configured, and otherwise filled by Deno as the server runs.
+    fn isolated_deno_dir(
+        &self,
+        language_server_id: &LanguageServerId,
+        settings: &WorkmanSettings,
+    ) -> Result<Option<String>> {
+        let isolated = &settings.isolated_deno_dir;
+        if !isolated.enabled {
+            return Ok(None);
+        }
+
+        let generation = &isolated.generation;
+        Self::check_dir_name("isolatedDenoDir.generation", generation)
+            .map_err(|err| format!("{language_server_id}: {err}"))?;
+        let name = format!("deno-dir-{generation}");
+        let deno_dir = Path::new(DENO_CACHE_DIR).join(&name);
+        if fs::metadata(&deno_dir).is_err() {
+            match &isolated.seed_url {
+                Some(url) => {
+                    zed::set_language_server_installation_status(
+                        language_server_id,
+                        &zed::LanguageServerInstallationStatus::Downloading,
+                    );
+                    let file_type = if url.ends_with(".zip") {
+                        zed::DownloadedFileType::Zip
+                    } else {
+                        zed::DownloadedFileType::GzipTar
+                    };
+                    let result =
+                        zed::download_file(url, &deno_dir.to_string_lossy(), file_type);
+                    if let Err(err) = result {
+                        zed::set_language_server_installation_status(
+                            language_server_id,
+                            &zed::LanguageServerInstallationStatus::Failed(err.clone()),
+                        );
+                        return Err(format!(
+                            "{language_server_id}: failed to seed DENO_DIR from {url}: {err}"
+                        ));
+                    }
+                    zed::set_language_server_installation_status(
+                        language_server_id,
+                        &zed::LanguageServerInstallationStatus::None,
+                    );
+                }
+                None => fs::create_dir_all(&deno_dir).map_err(|err| {
+                    format!("{language_server_id}: failed to create DENO_DIR: {err}")
+                })?,
+            }
+        }
+
+        if let Ok(entries) = fs::read_dir(DENO_CACHE_DIR) {
+            for entry in entries.flatten() {
+                if entry.file_name().to_str() != Some(name.as_str()) {
+                    fs::remove_dir_all(entry.path()).ok();
+                }
+            }
+        }
+
+        let deno_dir = env::current_dir()
+            .map_err(|err| format!("failed to get extension work directory: {err}"))?
+            .join(deno_dir);
+        Ok(Some(deno_dir.to_string_lossy().to_string()))
+    }
+
+    /// Checks that a setting used as part of a directory name cannot escape
+    /// the extension work directory.
+    fn check_dir_name(key: &str, value: &str) -> Result<()> {
+        if value.is_empty()
+            || value == "."
+            || value == ".."
+            || !value
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'))
         {
             return Err(format!(
-                "Workman LSP not found at {}",
-                server_path
+                "{SETTINGS_PATH}.{key} must only contain letters, digits, `.`, `_` and `-`, \
+                 found {value:?}"
             ));
         }
-        Ok((deno_config, server_path))
+        Ok(())
+    }
+
+    /// Builds the server environment: the worktree shell environment with the
+    /// isolated `DENO_DIR`, if any, and then the `env` setting applied on top.
+    ///
+    /// A string value adds or overrides a variable and `null` removes it.
+    /// Names are matched case-insensitively on Windows, like Windows does.
+    fn server_env(
+        &self,
+        worktree: &zed::Worktree,
+        settings: &WorkmanSettings,
+        deno_dir: Option<String>,
+    ) -> Result<zed::EnvVars> {
+        let case_insensitive = matches!(zed::current_platform().0, zed::Os::Windows);
+        let same_name = |a: &str, b: &str| {
+            if case_insensitive {
+                a.eq_ignore_ascii_case(b)
+            } else {
+                a == b
+            }
+        };
+
+        let mut env = worktree.shell_env();
+        if let Some(deno_dir) = deno_dir {
+            env.retain(|(key, _)| !same_name(key, "DENO_DIR"));
+            env.push(("DENO_DIR".to_string(), deno_dir));
+        }
+        for (name, value) in &settings.env {
+            env.retain(|(key, _)| !same_name(key, name));
+            if let Some(value) = value {
+                let value = self
+                    .expand(worktree, value, &[])
+                    .map_err(|err| format!("{SETTINGS_PATH}.env.{name}: {err}"))?;
+                env.push((name.clone(), value));
+            }
+        }
+        Ok(env)
+    }
+
+    /// Reads the project's own Workman configuration files.
+    ///
+    /// Later [`PROJECT_CONFIG_FILES`] override earlier ones. They override the
+    /// built-in defaults and are overridden by `lsp.workman-lsp.settings`. A
+    /// file that exists but is not a JSON object is an error rather than being
+    /// silently ignored.
+    fn project_configuration(&self, worktree: &zed::Worktree) -> Result<Option<serde_json::Value>> {
+        let mut configuration = None;
+        for path in PROJECT_CONFIG_FILES {
+            let Ok(contents) = worktree.read_text_file(path) else {
+                continue;
+            };
+            let value = serde_json::from_str::<serde_json::Value>(&contents)
+                .map_err(|err| format!("failed to parse {path}: {err}"))?;
+            if !value.is_object() {
+                return Err(format!("{path} must contain a JSON object"));
+            }
+            match &mut configuration {
+                Some(configuration) => deep_merge(configuration, value),
+                None => configuration = Some(value),
+            }
+        }
+        Ok(configuration)
+    }
+
+    /// The initialization options sent even when the user configures none.
+    ///
+    /// They describe what the extension resolved so the server does not have
+    /// to repeat the search. `resolution` is absent until the server has been
+    /// started for the worktree, and `stdlib` is then the same directory
+    /// [`Self::stdlib`] picked for the started server.
+    fn default_initialization_options(&self, worktree: &zed::Worktree) -> serde_json::Value {
+        let resolution = self.resolutions.get(&worktree.id());
+        let server_root = resolution.and_then(|resolution| Self::server_root(&resolution.server));
+        let stdlib = self
+            .servers
+            .get(&worktree.id())
+            .and_then(|server| server.stdlib.as_ref())
+            .map(|stdlib| stdlib.dir.clone());
+
+        let (os, arch) = zed::current_platform();
+        let os = match os {
+            zed::Os::Mac => "macos",
+            zed::Os::Linux => "linux",
+            zed::Os::Windows => "windows",
+        };
+        let arch = match arch {
+            zed::Architecture::Aarch64 => "aarch64",
+            zed::Architecture::X86 => "x86",
+            zed::Architecture::X8664 => "x86_64",
+        };
+
+        serde_json::json!({
+            "extension": {
+                "version": env!("CARGO_PKG_VERSION"),
+            },
+            "worktreeRoot": worktree.root_path(),
+            "serverRoot": server_root,
+            "stdlib": stdlib,
+            "platform": {
+                "os": os,
+                "arch": arch,
+            },
+            "resolution": resolution.map(Resolution::summary),
+        })
+    }
+
+    /// Looks up an environment variable as the user's shell sees it.
+    ///
+    /// The worktree shell environment takes precedence; the extension's own
+    /// process environment is only consulted when the shell does not define
+    /// the variable. Empty values count as unset.
+    fn env_var(&self, worktree: &zed::Worktree, name: &str) -> Option<String> {
+        Self::lookup_env(&worktree.shell_env(), name)
+    }
+
+    /// [`Self::env_var`] for a shell environment that has already been fetched.
+    fn lookup_env(shell_env: &zed::EnvVars, name: &str) -> Option<String> {
+        shell_env
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.clone())
+            .filter(|value| !value.is_empty())
+            .or_else(|| env::var(name).ok().filter(|value| !value.is_empty()))
+    }
+
+    fn server_from_root(&self, worktree: &zed::Worktree, root: PathBuf) -> Result<String> {
+        let server_path = Self::checkout_server(&root);
+        self.check_file(worktree, Path::new(&server_path))?;
+        Ok(server_path)
+    }
+
+    fn checkout_server(root: &Path) -> String {
+        root.join("lsp")
+            .join("server")
+            .join("src")
+            .join("server.ts")
+            .to_string_lossy()
+            .to_string()
+    }
+
+    /// Looks for a Workman checkout inside the worktree.
+    ///
+    /// Candidates are the worktree root, `serverSearchPaths` and the built-in
+    /// [`SERVER_SEARCH_PATHS`]. The nearest one wins: candidates are ordered
+    /// by how many directories below the worktree root they are, and ties
+    /// keep the order above.
+    ///
+    /// Directories above the worktree are not searched, since the sandbox
+    /// cannot read files there. A subfolder of a checkout needs `serverRoot`
+    /// pointing at the checkout, such as `${worktreeRoot}/..`.
+    fn discover_server(
+        &self,
+        worktree: &zed::Worktree,
+        settings: &WorkmanSettings,
+    ) -> Result<String> {
+        let root = PathBuf::from(worktree.root_path());
+
+        let mut candidates = vec![PathBuf::new()];
+        let subpaths = settings
+            .server_search_paths
+            .iter()
+            .map(String::as_str)
+            .chain(SERVER_SEARCH_PATHS.iter().copied());
+        for subpath in subpaths {
+            let relative = PathBuf::from(subpath.trim_matches('/'));
+            if !candidates.contains(&relative) {
+                candidates.push(relative);
+            }
+        }
+        candidates.sort_by_key(|relative| relative.components().count());
+
+        for relative in &candidates {
+            let script = relative.join("lsp").join("server").join("src").join("server.ts");
+            if worktree.read_text_file(&script.to_string_lossy()).is_ok() {
+                return Ok(Self::checkout_server(&root.join(relative)));
+            }
+        }
+
+        Err(format!(
+            "no `lsp/server/src/server.ts` found in the worktree root or {}; set \
+             {SETTINGS_PATH}.serverRoot when the checkout is above the worktree",
+            candidates[1..]
+                .iter()
+                .map(|relative| format!("`{}`", relative.display()))
+                .collect::<Vec<_>>()
+                .join(", "),
+        ))
+    }
+
+    /// Installs a released server bundle into the extension work directory.
+    ///
+    /// The bundle is an archive of `lsp/server`, so it unpacks to a directory
+    /// containing `deno.json` (or `deno.jsonc`) and `src/server.ts`.
+    /// `serverDownloadUrl` (and optionally `serverVersion`) replace the GitHub
+    /// release lookup, which is how a locally served release fixture is
+    /// installed. Without `serverVersion` the install directory is named after
+    /// a hash of the URL, so pointing it somewhere else installs afresh.
+    ///
+    /// The installation status is cleared on success and set to failed on
+    /// any error, so Zed never keeps showing a check or download as running.
+    fn server_from_download(
+        &self,
+        language_server_id: &LanguageServerId,
+        settings: &WorkmanSettings,
+    ) -> Result<String> {
+        zed::set_language_server_installation_status(
+            language_server_id,
+            &zed::LanguageServerInstallationStatus::CheckingForUpdate,
+        );
+        let result = self.install_server_bundle(language_server_id, settings);
+        let status = match &result {
+            Ok(_) => zed::LanguageServerInstallationStatus::None,
+            Err(err) => zed::LanguageServerInstallationStatus::Failed(err.clone()),
+        };
+        zed::set_language_server_installation_status(language_server_id, &status);
+        result
+    }
+
+    /// [`Self::server_from_download`] without the status bookkeeping.
+    fn install_server_bundle(
+        &self,
+        language_server_id: &LanguageServerId,
+        settings: &WorkmanSettings,
+    ) -> Result<String> {
+        let (version, url) = match &settings.server_download_url {
+            Some(url) => {
+                let version = match &settings.server_version {
+                    Some(version) => {
+                        Self::check_dir_name("serverVersion", version)?;
+                        version.clone()
+                    }
+                    None => format!("custom-{:016x}", fnv1a(url.as_bytes())),
+                };
+                (version, url.clone())
+            }
+            None => match self.latest_release() {
+                Ok(release) => release,
+                Err(err) => {
+                    // Offline: fall back to whatever was installed last.
+                    return self.installed_bundle().ok_or(err);
+                }
+            },
+        };
+
+        let server_dir = format!("{SERVER_DIR_PREFIX}{version}");
+        if !Self::bundle_installed(&server_dir) {
+            zed::set_language_server_installation_status(
+                language_server_id,
+                &zed::LanguageServerInstallationStatus::Downloading,
+            );
+
+            let file_type = if url.ends_with(".zip") {
+                zed::DownloadedFileType::Zip
+            } else {
+                zed::DownloadedFileType::GzipTar
+            };
+            fs::remove_dir_all(&server_dir).ok();
+            zed::download_file(&url, &server_dir, file_type)
+                .map_err(|err| format!("failed to download {url}: {err}"))?;
+            fs::write(Path::new(&server_dir).join(INSTALLED_MARKER), &version)
+                .map_err(|err| format!("failed to record installed version: {err}"))?;
+
+            Self::remove_stale_bundles(&server_dir);
+        }
+
+        self.server_from_bundle(&server_dir)
+    }
+
+    fn latest_release(&self) -> Result<(String, String)> {
+        let release = zed::latest_github_release(
+            SERVER_REPO,
+            zed::GithubReleaseOptions {
+                require_assets: true,
+                pre_release: false,
+            },
+        )?;
+        let asset = release
+            .assets
+            .iter()
+            .find(|asset| asset.name == SERVER_ASSET)
+            .ok_or_else(|| format!("no asset found matching {SERVER_ASSET:?}"))?;
+        Ok((release.version, asset.download_url.clone()))
+    }
+
+    fn bundle_installed(server_dir: &str) -> bool {
+        fs::metadata(Path::new(server_dir).join(INSTALLED_MARKER))
+            .is_ok_and(|stat| stat.is_file())
+    }
+
+    fn installed_bundle(&self) -> Option<String> {
+        let mut installed = fs::read_dir(".")
+            .ok()?
+            .flatten()
+            .filter_map(|entry| entry.file_name().into_string().ok())
+            .filter(|name| name.starts_with(SERVER_DIR_PREFIX) && Self::bundle_installed(name))
+            .collect::<Vec<_>>();
+        installed.sort();
+        self.server_from_bundle(installed.last()?).ok()
+    }
+
+    fn remove_stale_bundles(current_dir: &str) {
+        let Ok(entries) = fs::read_dir(".") else {
+            return;
+        };
+        for entry in entries.flatten() {
+            if let Some(name) = entry.file_name().to_str() {
+                if name.starts_with(SERVER_DIR_PREFIX) && name != current_dir {
+                    fs::remove_dir_all(entry.path()).ok();
+                }
+            }
+        }
+    }
+
+    /// The standard library of every started language server, provided they
+    /// all share one.
+    ///
+    /// Requests that come without a worktree cannot tell projects apart, so
+    /// rather than guess they only work while that is unambiguous.
+    fn shared_stdlib(&self) -> Result<&Stdlib> {
+        let mut stdlibs = self
+            .servers
+            .values()
+            .filter_map(|server| server.stdlib.as_ref());
+        let stdlib = stdlibs.next().ok_or_else(|| {
+            "no Workman standard library is known yet; open a Workman file first".to_string()
+        })?;
+        if stdlibs.any(|other| other.dir != stdlib.dir) {
+            return Err(
+                "projects with different Workman standard libraries are open, so which one \
+                 is meant is ambiguous"
+                    .to_string(),
+            );
+        }
+        Ok(stdlib)
+    }
+
+    fn server_from_bundle(&self, server_dir: &str) -> Result<String> {
+        let server_path = env::current_dir()
+            .map_err(|err| format!("failed to get extension work directory: {err}"))?
+            .join(server_dir)
+            .join("src")
+            .join("server.ts");
+        fs::metadata(&server_path)
+            .map_err(|err| format!("`{}` is not readable: {err}", server_path.display()))?;
+        Ok(server_path.to_string_lossy().to_string())
     }
 }
 
 impl zed::Extension for WorkmanExtension {
     fn new() -> Self {
-        Self
+        Self::default()
     }
 
     fn language_server_command(
@@ -105,51 +1595,77 @@ impl zed::Extension for WorkmanExtension {
         language_server_id: &LanguageServerId,
         worktree: &zed::Worktree,
     ) -> Result<zed::Command> {
-        let deno = self.resolve_deno_binary(language_server_id, worktree)?;
-        let (deno_config, server_path) = self.resolve_server_paths(worktree)?;
+        let lsp_settings = LspSettings::for_worktree("workman-lsp", worktree).unwrap_or_default();
+        let fingerprint = self.fingerprint(worktree, &lsp_settings);
+        let mut settings = self
+            .settings(worktree, &lsp_settings, &fingerprint)
+            .and_then(|settings| self.expand_settings(worktree, settings))
+            .map_err(|err| format!("{language_server_id}: {err}"))?;
+        let Resolution {
+            server,
+            entrypoint,
+            deno_config,
+            runtime_binary,
+            ..
+        } = self.resolution(language_server_id, worktree, fingerprint, &settings)?;
 
-        let args = if let Ok(lsp_settings) = LspSettings::for_worktree("workman-lsp", worktree) {
-            if let Some(binary) = lsp_settings.binary {
-                if let Some(arguments) = binary.arguments {
-                    arguments
-                } else {
-                    vec![
-                        "run".to_string(),
-                        "--allow-all".to_string(),
-                        "--config".to_string(),
-                        deno_config,
-                        server_path,
-                    ]
-                }
-            } else {
-                vec![
-                    "run".to_string(),
-                    "--allow-all".to_string(),
-                    "--config".to_string(),
-                    deno_config,
-                    server_path,
-                ]
-            }
-        } else {
-            vec![
-                "run".to_string(),
-                "--allow-all".to_string(),
-                "--config".to_string(),
-                deno_config,
-                server_path,
-            ]
+        let server_root = Self::server_root(&server);
+        let mut resolved = vec![("serverPath", entrypoint.as_str())];
+        if let Some(server_root) = &server_root {
+            resolved.push(("serverRoot", server_root.as_str()));
+        }
+        if let Some(deno_config) = &deno_config {
+            resolved.push(("denoConfig", deno_config.as_str()));
+        }
+        let expand_all = |args: Vec<String>| {
+            args.iter()
+                .map(|arg| self.expand(worktree, arg, &resolved))
+                .collect::<Result<Vec<_>>>()
+                .map_err(|err| format!("{language_server_id}: {err}"))
         };
 
-        let env = match zed::current_platform().0 {
-            zed::Os::Mac | zed::Os::Linux => worktree.shell_env(),
-            zed::Os::Windows => Default::default(),
+        let deno_dir = match (&runtime_binary, settings.runtime) {
+            (Some(_), Runtime::Deno) => self.isolated_deno_dir(language_server_id, &settings)?,
+            _ => None,
         };
+        let env = self
+            .server_env(worktree, &settings, deno_dir)
+            .map_err(|err| format!("{language_server_id}: {err}"))?;
 
-        Ok(zed::Command {
-            command: deno,
-            args,
-            env,
-        })
+        let custom_arguments = lsp_settings.binary.and_then(|binary| binary.arguments);
+
+        let stdlib = self.stdlib(worktree, &settings, server_root.as_deref(), env.clone());
+        let (command, args) = match runtime_binary {
+            None => {
+                let args = match custom_arguments {
+                    Some(arguments) => expand_all(arguments)?,
+                    None => expand_all(settings.server_args)?,
+                };
+                (entrypoint.clone(), args)
+            }
+            Some(runtime) => {
+                let args = match custom_arguments {
+                    Some(arguments) => expand_all(arguments)?,
+                    None => {
+                        settings.runtime_args = expand_all(settings.runtime_args)?;
+                        settings.server_args = expand_all(settings.server_args)?;
+                        self.server_arguments(
+                            worktree,
+                            &settings,
+                            server_root.as_deref(),
+                            stdlib.as_ref().map(|stdlib| stdlib.dir.as_str()),
+                            deno_config,
+                            &entrypoint,
+                        )
+                    }
+                };
+                (runtime, args)
+            }
+        };
+
+        let command = zed::Command { command, args, env };
+        self.servers.insert(worktree.id(), LaunchedServer { stdlib });
+        Ok(command)
     }
 
     fn language_server_initialization_options(
@@ -157,20 +1673,234 @@ impl zed::Extension for WorkmanExtension {
         _language_server_id: &LanguageServerId,
         worktree: &zed::Worktree,
     ) -> Result<Option<serde_json::Value>> {
-        Ok(LspSettings::for_worktree("workman-lsp", worktree)
+        let mut options = self.default_initialization_options(worktree);
+        if let Some(user_options) = LspSettings::for_worktree("workman-lsp", worktree)
             .ok()
-            .and_then(|settings| settings.initialization_options))
+            .and_then(|settings| settings.initialization_options)
+        {
+            deep_merge(&mut options, user_options);
+        }
+        Ok(Some(options))
     }
 
     fn language_server_workspace_configuration(
         &mut self,
-        _language_server_id: &LanguageServerId,
+        language_server_id: &LanguageServerId,
         worktree: &zed::Worktree,
     ) -> Result<Option<serde_json::Value>> {
-        Ok(LspSettings::for_worktree("workman-lsp", worktree)
+        let user_settings = LspSettings::for_worktree("workman-lsp", worktree)
             .ok()
-            .and_then(|settings| settings.settings))
+            .and_then(|settings| settings.settings);
+        let project_configuration = self
+            .project_configuration(worktree)
+            .map_err(|err| format!("{language_server_id}: {err}"))?;
+
+        let mut configuration = default_workspace_configuration();
+        for overlay in [project_configuration, user_settings].into_iter().flatten() {
+            deep_merge(&mut configuration, overlay);
+        }
+        Ok(Some(configuration))
+    }
+
+    fn label_for_completion(
+        &self,
+        _language_server_id: &LanguageServerId,
+        completion: zed::lsp::Completion,
+    ) -> Option<zed::CodeLabel> {
+        labels::completion_label(&completion)
+    }
+
+    fn label_for_symbol(
+        &self,
+        _language_server_id: &LanguageServerId,
+        symbol: zed::lsp::Symbol,
+    ) -> Option<zed::CodeLabel> {
+        labels::symbol_label(&symbol)
+    }
+
+    fn run_slash_command(
+        &self,
+        command: zed::SlashCommand,
+        args: Vec<String>,
+        worktree: Option<&zed::Worktree>,
+    ) -> Result<zed::SlashCommandOutput> {
+        let worktree = worktree.ok_or_else(|| format!("/{} needs a project", command.name))?;
+        match command.name.as_str() {
+            "wm-docs" => {
+                let query = args.join(" ");
+                let query = query.trim();
+                if query.is_empty() {
+                    return Err("/wm-docs needs a module or declaration name".to_string());
+                }
+                let started = self
+                    .servers
+                    .get(&worktree.id())
+                    .and_then(|server| server.stdlib.as_ref());
+                let discovered;
+                let stdlib = match started {
+                    Some(stdlib) => stdlib,
+                    None => {
+                        discovered = self.discover_stdlib(worktree)?;
+                        &discovered
+                    }
+                };
+                let sections = docs::lookup(stdlib.docs()?, query);
+                if sections.is_empty() {
+                    return Err(format!("no Workman module or declaration named `{query}`"));
+                }
+                Ok(slash_commands::sections_output(
+                    sections
+                        .into_iter()
+                        .map(|(title, markdown)| (format!("Workman docs: {title}"), markdown)),
+                ))
+            }
+            name => Err(format!("unknown slash command: /{name}")),
+        }
+    }
+
+    fn complete_slash_command_argument(
+        &self,
+        command: zed::SlashCommand,
+        args: Vec<String>,
+    ) -> Result<Vec<zed::SlashCommandArgumentCompletion>> {
+        match command.name.as_str() {
+            "wm-docs" => {
+                let modules = self.shared_stdlib()?.docs()?;
+                Ok(slash_commands::module_completions(
+                    modules.iter().map(|module| module.name.as_str()),
+                    args.join(" ").trim(),
+                ))
+            }
+            _ => Ok(Vec::new()),
+        }
+    }
+
+    fn suggest_docs_packages(&self, provider: String) -> Result<Vec<String>> {
+        match provider.as_str() {
+            DOCS_PROVIDER => Ok(vec![DOCS_PACKAGE.to_string()]),
+            _ => Ok(Vec::new()),
+        }
     }
+
+    /// Indexes the standard library the started language servers share,
+    /// since indexing does not happen for a particular worktree.
+    fn index_docs(
+        &self,
+        provider: String,
+        package: String,
+        database: &zed::KeyValueStore,
+    ) -> Result<()> {
+        if provider != DOCS_PROVIDER || package != DOCS_PACKAGE {
+            return Err(format!("no Workman docs for {provider}/{package}"));
+        }
+        let modules = self.shared_stdlib()?.docs()?;
+        for (key, markdown) in docs::index_entries(modules) {
+            database.insert(&key, &markdown)?;
+        }
+        Ok(())
+    }
+}
+
+/// The 64-bit FNV-1a hash of `bytes`, which unlike the standard library's
+/// hashers is the same across Rust releases and so can name directories.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0000_0100_0000_01b3)
+    })
 }
 
 zed::register_extension!(WorkmanExtension);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resolve(path: &str) -> Result<String> {
+        WorkmanExtension::resolve_path("/work/app", Some("/home/me"), path)
+    }
+
+    #[test]
+    fn resolves_relative_and_home_paths() {
+        assert_eq!(resolve("tools/workman").unwrap(), "/work/app/tools/workman");
+        assert_eq!(resolve("./a/../b/").unwrap(), "/work/app/b");
+        assert_eq!(resolve("..").unwrap(), "/work");
+        assert_eq!(resolve("/opt/workman/./lsp").unwrap(), "/opt/workman/lsp");
+        assert_eq!(resolve("~").unwrap(), "/home/me");
+        assert_eq!(resolve("~/workman").unwrap(), "/home/me/workman");
+        assert_eq!(resolve("$HOME/workman").unwrap(), "/home/me/workman");
+        assert_eq!(resolve("~workman").unwrap(), "/work/app/~workman");
+        assert_eq!(
+            WorkmanExtension::resolve_path("/work", None, "~/workman").unwrap_err(),
+            "cannot expand \"~/workman\": HOME is not set"
+        );
+    }
+
+    #[test]
+    fn resolves_windows_paths() {
+        assert_eq!(resolve("C:\\src\\workman").unwrap(), "C:/src/workman");
+        assert_eq!(resolve("d:/src/../workman").unwrap(), "d:/workman");
+        assert_eq!(
+            resolve("\\\\server\\share\\workman\\..\\..").unwrap(),
+            "//server/share"
+        );
+        let windows = |path| {
+            WorkmanExtension::resolve_path("C:\\work\\app", Some("C:\\Users\\me"), path)
+                .unwrap()
+        };
+        assert_eq!(windows("tools\\workman"), "C:/work/app/tools/workman");
+        assert_eq!(windows("..\\workman"), "C:/work/workman");
+        assert_eq!(windows("~\\workman"), "C:/Users/me/workman");
+    }
+
+    #[test]
+    fn reports_numbered_attempts() {
+        assert_eq!(
+            WorkmanExtension::report(
+                "could not find deno",
+                &["PATH: not found".to_string(), "download: disabled".to_string()]
+            ),
+            "could not find deno. Tried:\n  1. PATH: not found\n  2. download: disabled"
+        );
+    }
+
+    #[test]
+    fn server_root_depends_on_the_layout() {
+        let root = |server| WorkmanExtension::server_root(&server);
+        assert_eq!(
+            root(ServerEntry::Script("/src/workman/lsp/server/src/server.ts".to_string())),
+            Some("/src/workman".to_string())
+        );
+        assert_eq!(
+            root(ServerEntry::Script("/ext/workman-lsp-1.0/src/server.ts".to_string())),
+            Some("/ext/workman-lsp-1.0".to_string())
+        );
+        assert_eq!(
+            root(ServerEntry::Script("C:/src/workman/lsp/server/src/server.ts".to_string())),
+            Some("C:/src/workman".to_string())
+        );
+        assert_eq!(root(ServerEntry::Specifier("jsr:@workman/lsp".to_string())), None);
+        assert_eq!(root(ServerEntry::Binary("/usr/bin/workman-lsp".to_string())), None);
+    }
+
+    #[test]
+    fn dir_names_cannot_escape() {
+        for valid in ["1", "v1.2.3", "nightly_2024-01-01"] {
+            assert!(WorkmanExtension::check_dir_name("serverVersion", valid).is_ok());
+        }
+        for invalid in ["", ".", "..", "../x", "a/b", "a\\b", "1 2"] {
+            assert!(WorkmanExtension::check_dir_name("serverVersion", invalid).is_err());
+        }
+        assert_eq!(
+            WorkmanExtension::check_dir_name("serverVersion", "a/b").unwrap_err(),
+            "lsp.workman-lsp.settings.serverVersion must only contain letters, digits, `.`, \
+             `_` and `-`, found \"a/b\""
+        );
+    }
+
+    #[test]
+    fn fnv1a_is_stable() {
+        assert_eq!(fnv1a(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1a(b"a"), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(fnv1a(b"foobar"), 0x8594_4171_f739_67e8);
+    }
+}